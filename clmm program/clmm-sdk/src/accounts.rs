@@ -0,0 +1,60 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::ZeroCopy;
+use clmm_basic::states::config::AmmConfig;
+use clmm_basic::states::tick_array::TickArrayState;
+use clmm_basic::states::tick_array_bitmap_extension::{
+    TickArrayBitmapExtension, POOL_TICK_ARRAY_BITMAP_SEED,
+};
+use clmm_basic::PoolState;
+
+pub use clmm_basic::states::tick_array::TICK_ARRAY_SEED;
+
+/// Copies a zero-copy account out of its raw data, checking the discriminator
+pub fn deserialize_zero_copy<T: ZeroCopy>(data: &[u8]) -> Result<T> {
+    let discriminator_len = T::DISCRIMINATOR.len();
+    if !data.starts_with(T::DISCRIMINATOR) {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    let end = discriminator_len + std::mem::size_of::<T>();
+    if data.len() < end {
+        return Err(ErrorCode::AccountDidNotDeserialize.into());
+    }
+    Ok(bytemuck::pod_read_unaligned(&data[discriminator_len..end]))
+}
+
+pub fn deserialize_pool_state(data: &[u8]) -> Result<PoolState> {
+    deserialize_zero_copy(data)
+}
+
+pub fn deserialize_tick_array_state(data: &[u8]) -> Result<TickArrayState> {
+    deserialize_zero_copy(data)
+}
+
+pub fn deserialize_tick_array_bitmap_extension(data: &[u8]) -> Result<TickArrayBitmapExtension> {
+    deserialize_zero_copy(data)
+}
+
+pub fn deserialize_amm_config(mut data: &[u8]) -> Result<AmmConfig> {
+    AmmConfig::try_deserialize(&mut data)
+}
+
+pub fn tick_array_address(pool_id: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_id.as_ref(),
+            &start_tick_index.to_be_bytes(),
+        ],
+        &clmm_basic::id(),
+    )
+    .0
+}
+
+pub fn tick_array_bitmap_extension_address(pool_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_id.as_ref()],
+        &clmm_basic::id(),
+    )
+    .0
+}
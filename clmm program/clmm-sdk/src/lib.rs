@@ -0,0 +1,8 @@
+//! Off-chain quoting for clmm_basic pools. Decodes raw account data and runs the
+//! program's own swap_internal on copies of it, without a validator.
+
+pub mod accounts;
+pub mod quote;
+
+pub use accounts::*;
+pub use quote::*;
@@ -0,0 +1,366 @@
+use anchor_lang::prelude::*;
+use clmm_basic::instructions::swap::swap_internal;
+use clmm_basic::libraries::liquidity_math::Q64;
+use clmm_basic::libraries::tick_math;
+use clmm_basic::states::config::AmmConfig;
+use clmm_basic::states::tick_array::TickArrayState;
+use clmm_basic::states::tick_array_bitmap_extension::TickArrayBitmapExtension;
+use clmm_basic::PoolState;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// Outcome of a simulated swap. Amounts are what the vaults see, so callers holding
+/// Token-2022 mints with a transfer fee still need to apply it on both legs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapQuote {
+    /// Input taken by the pool, fees included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Trading fee paid, protocol share included
+    pub fee_amount: u64,
+    pub sqrt_price_x64: u128,
+    pub tick: i32,
+    pub ticks_crossed: u32,
+    /// Relative shortfall of the execution price against the spot price before the swap
+    pub price_impact: f64,
+    /// Start indexes of the tick arrays the swap reads, in order. The first one is the
+    /// swap's `tick_array` account and the rest go in remaining_accounts
+    pub tick_array_start_indexes: Vec<i32>,
+}
+
+/// Start indexes of the first `count` initialized tick arrays a swap in this
+/// direction would read, for fetching them before quoting
+pub fn swap_tick_array_start_indexes(
+    pool_state: &PoolState,
+    tick_array_bitmap_extension: &TickArrayBitmapExtension,
+    zero_for_one: bool,
+    count: usize,
+) -> Result<Vec<i32>> {
+    let mut start_indexes = Vec::with_capacity(count);
+    let mut next_start_index =
+        first_tick_array_start_index(pool_state, tick_array_bitmap_extension, zero_for_one)?;
+    while let Some(start_index) = next_start_index {
+        if start_indexes.len() == count {
+            break;
+        }
+        start_indexes.push(start_index);
+        next_start_index = pool_state.next_initialized_tick_array_start_index(
+            tick_array_bitmap_extension,
+            start_index,
+            zero_for_one,
+        )?;
+    }
+    Ok(start_indexes)
+}
+
+/// The array holding the current tick if initialized, else the next initialized one
+fn first_tick_array_start_index(
+    pool_state: &PoolState,
+    tick_array_bitmap_extension: &TickArrayBitmapExtension,
+    zero_for_one: bool,
+) -> Result<Option<i32>> {
+    let current_start_index =
+        TickArrayState::get_array_start_index(pool_state.current_tick, pool_state.tick_spacing);
+    if pool_state
+        .is_tick_array_initialized(Some(tick_array_bitmap_extension), current_start_index)?
+    {
+        Ok(Some(current_start_index))
+    } else {
+        pool_state.next_initialized_tick_array_start_index(
+            tick_array_bitmap_extension,
+            current_start_index,
+            zero_for_one,
+        )
+    }
+}
+
+/// Runs the program's swap_internal on copies of the decoded accounts. `tick_arrays`
+/// may be given in any order; a swap that needs an array missing from it fails with
+/// NotEnoughTickArrayAccount, as it would on chain. A zero `sqrt_price_limit_x64`
+/// means no limit
+#[allow(clippy::too_many_arguments)]
+pub fn quote_swap(
+    amm_config: &AmmConfig,
+    pool_state: &PoolState,
+    tick_array_bitmap_extension: &TickArrayBitmapExtension,
+    tick_arrays: &[TickArrayState],
+    amount_specified: u64,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> Result<SwapQuote> {
+    let pool_sqrt_price_x64 = pool_state.sqrt_price_x64;
+    let sqrt_price_limit_x64 = if sqrt_price_limit_x64 == 0 {
+        if zero_for_one {
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        } else {
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        }
+    } else {
+        sqrt_price_limit_x64
+    };
+
+    // swap_internal takes the initialized arrays in swap order, so line up the given
+    // ones until the first that is missing
+    let mut tick_array_start_indexes = Vec::new();
+    let mut tick_array_copies = Vec::new();
+    let mut next_start_index =
+        first_tick_array_start_index(pool_state, tick_array_bitmap_extension, zero_for_one)?;
+    while let Some(start_index) = next_start_index {
+        let Some(tick_array) = tick_arrays
+            .iter()
+            .find(|tick_array| tick_array.start_tick_index == start_index)
+        else {
+            break;
+        };
+        tick_array_start_indexes.push(start_index);
+        tick_array_copies.push(RefCell::new(*tick_array));
+        next_start_index = pool_state.next_initialized_tick_array_start_index(
+            tick_array_bitmap_extension,
+            start_index,
+            zero_for_one,
+        )?;
+    }
+    let mut tick_array_states: VecDeque<_> =
+        tick_array_copies.iter().map(RefCell::borrow_mut).collect();
+
+    // The timestamp only feeds reward bookkeeping on the copies, never the amounts
+    let pool_state_copy = RefCell::new(*pool_state);
+    let pool_state_after = &mut pool_state_copy.borrow_mut();
+    let swap_result = swap_internal(
+        amm_config,
+        pool_state_after,
+        &tick_array_bitmap_extension.pool_id,
+        None,
+        &mut tick_array_states,
+        tick_array_bitmap_extension,
+        amount_specified,
+        sqrt_price_limit_x64,
+        zero_for_one,
+        is_base_input,
+        0,
+    )?;
+    // Every array swap_internal popped was read
+    tick_array_start_indexes.truncate(tick_array_copies.len() - tick_array_states.len());
+
+    let (amount_in, amount_out) = if zero_for_one {
+        (swap_result.amount_0, swap_result.amount_1)
+    } else {
+        (swap_result.amount_1, swap_result.amount_0)
+    };
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out,
+        fee_amount: swap_result.fee_amount,
+        sqrt_price_x64: pool_state_after.sqrt_price_x64,
+        tick: pool_state_after.current_tick,
+        ticks_crossed: swap_result.ticks_crossed,
+        price_impact: price_impact(pool_sqrt_price_x64, amount_in, amount_out, zero_for_one),
+        tick_array_start_indexes,
+    })
+}
+
+/// 1 - execution price / spot price, with both prices in output per input
+pub fn price_impact(
+    sqrt_price_x64: u128,
+    amount_in: u64,
+    amount_out: u64,
+    zero_for_one: bool,
+) -> f64 {
+    if amount_in == 0 {
+        return 0.0;
+    }
+    let sqrt_price = sqrt_price_x64 as f64 / Q64 as f64;
+    let price_1_per_0 = sqrt_price * sqrt_price;
+    let spot_price = if zero_for_one {
+        price_1_per_0
+    } else {
+        1.0 / price_1_per_0
+    };
+    1.0 - amount_out as f64 / amount_in as f64 / spot_price
+}
+
+#[cfg(test)]
+mod quote_test {
+    use super::*;
+    use clmm_basic::errors::ClmmError;
+    use clmm_basic::libraries::liquidity_math::{
+        get_delta_amount_0_unsigned, get_delta_amount_1_unsigned,
+    };
+    use clmm_basic::libraries::swap_math::FEE_RATE_DENOMINATOR_VALUE;
+    use clmm_basic::libraries::tick_math::get_sqrt_price_at_tick;
+
+    const TICK_SPACING: u16 = 10;
+    const TRADE_FEE_RATE: u32 = 3_000;
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+    const TICK_LOWER: i32 = -300;
+    const TICK_UPPER: i32 = 300;
+
+    /// Pool at tick 0 with a single position over [-300, 300), whose ticks live in
+    /// the arrays starting at -600 and 0
+    fn pool() -> (
+        AmmConfig,
+        PoolState,
+        TickArrayBitmapExtension,
+        Vec<TickArrayState>,
+    ) {
+        let pool_id = Pubkey::new_unique();
+        let amm_config = AmmConfig {
+            trade_fee_rate: TRADE_FEE_RATE,
+            tick_spacing: TICK_SPACING,
+            ..Default::default()
+        };
+        let mut pool_state = PoolState {
+            sqrt_price_x64: get_sqrt_price_at_tick(0).unwrap(),
+            liquidity: LIQUIDITY,
+            tick_spacing: TICK_SPACING,
+            current_tick: 0,
+            ..Default::default()
+        };
+        let mut tick_array_bitmap_extension: TickArrayBitmapExtension =
+            bytemuck::Zeroable::zeroed();
+        tick_array_bitmap_extension.pool_id = pool_id;
+
+        let mut tick_arrays = Vec::new();
+        for (tick, liquidity_net) in [
+            (TICK_LOWER, LIQUIDITY as i128),
+            (TICK_UPPER, -(LIQUIDITY as i128)),
+        ] {
+            let start_index = TickArrayState::get_array_start_index(tick, TICK_SPACING);
+            let mut tick_array: TickArrayState = bytemuck::Zeroable::zeroed();
+            tick_array.pool_id = pool_id;
+            tick_array.start_tick_index = start_index;
+            tick_array.initialized_tick_count = 1;
+            let tick_state = tick_array.get_tick_state_mut(tick, TICK_SPACING).unwrap();
+            tick_state.tick = tick;
+            tick_state.liquidity_net = liquidity_net;
+            tick_state.liquidity_gross = LIQUIDITY;
+            pool_state.flip_tick_array_bit(None, start_index).unwrap();
+            tick_arrays.push(tick_array);
+        }
+        (
+            amm_config,
+            pool_state,
+            tick_array_bitmap_extension,
+            tick_arrays,
+        )
+    }
+
+    #[test]
+    fn stops_before_the_next_initialized_tick() {
+        let (amm_config, pool_state, extension, tick_arrays) = pool();
+        let quote = quote_swap(
+            &amm_config,
+            &pool_state,
+            &extension,
+            &tick_arrays,
+            1_000_000,
+            0,
+            true,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(quote.amount_in, 1_000_000);
+        assert!(quote.amount_out > 0 && quote.amount_out < quote.amount_in);
+        assert_eq!(quote.ticks_crossed, 0);
+        // Array 0 holds nothing at or below tick 0, the next tick down is found in -600
+        assert_eq!(quote.tick_array_start_indexes, vec![0, -600]);
+        assert!(quote.tick < 0 && quote.tick > TICK_LOWER);
+    }
+
+    #[test]
+    fn crosses_into_the_next_tick_array() {
+        let (amm_config, pool_state, extension, tick_arrays) = pool();
+        let amount_specified = u64::MAX / 2;
+        let quote = quote_swap(
+            &amm_config,
+            &pool_state,
+            &extension,
+            &tick_arrays,
+            amount_specified,
+            0,
+            true,
+            true,
+        )
+        .unwrap();
+
+        // The whole range is consumed, past it there is no liquidity left to trade with
+        let sqrt_price_lower_x64 = get_sqrt_price_at_tick(TICK_LOWER).unwrap();
+        let sqrt_price_current_x64 = get_sqrt_price_at_tick(0).unwrap();
+        let amount_in_net = get_delta_amount_0_unsigned(
+            sqrt_price_lower_x64,
+            sqrt_price_current_x64,
+            LIQUIDITY,
+            true,
+        )
+        .unwrap();
+        let fee_amount = (u128::from(amount_in_net) * u128::from(TRADE_FEE_RATE))
+            .div_ceil(u128::from(FEE_RATE_DENOMINATOR_VALUE - TRADE_FEE_RATE))
+            as u64;
+        assert_eq!(quote.fee_amount, fee_amount);
+        assert_eq!(quote.amount_in, amount_in_net + fee_amount);
+        assert_eq!(
+            quote.amount_out,
+            get_delta_amount_1_unsigned(
+                sqrt_price_lower_x64,
+                sqrt_price_current_x64,
+                LIQUIDITY,
+                false
+            )
+            .unwrap()
+        );
+        assert_eq!(quote.ticks_crossed, 1);
+        assert_eq!(quote.tick_array_start_indexes, vec![0, -600]);
+        assert_eq!(quote.sqrt_price_x64, tick_math::MIN_SQRT_PRICE_X64 + 1);
+    }
+
+    #[test]
+    fn missing_tick_array_fails_as_on_chain() {
+        let (amm_config, pool_state, extension, tick_arrays) = pool();
+        let result = quote_swap(
+            &amm_config,
+            &pool_state,
+            &extension,
+            &tick_arrays[1..],
+            u64::MAX / 2,
+            0,
+            true,
+            true,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            ClmmError::NotEnoughTickArrayAccount.into()
+        );
+    }
+
+    #[test]
+    fn zero_amount_is_rejected_as_on_chain() {
+        let (amm_config, pool_state, extension, tick_arrays) = pool();
+        let result = quote_swap(
+            &amm_config,
+            &pool_state,
+            &extension,
+            &tick_arrays,
+            0,
+            0,
+            true,
+            true,
+        );
+        assert_eq!(result.unwrap_err(), ClmmError::ZeroAmountSpecified.into());
+    }
+
+    #[test]
+    fn lists_tick_arrays_in_swap_order() {
+        let (_, pool_state, extension, _) = pool();
+        assert_eq!(
+            swap_tick_array_start_indexes(&pool_state, &extension, true, 3).unwrap(),
+            vec![0, -600]
+        );
+        assert_eq!(
+            swap_tick_array_start_indexes(&pool_state, &extension, false, 3).unwrap(),
+            vec![0]
+        );
+    }
+}
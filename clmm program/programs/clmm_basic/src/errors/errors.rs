@@ -1,27 +0,0 @@
-use anchor_lang::prelude::*;
-
-#[error_code]
-pub enum ClmmError {
-    #[msg("Max Token Overflow")]
-    MaxTokenOverflow,
-    #[msg("Liquidity Subtraction Error")]
-    LiquiditySubValueError,
-    #[msg("Liquidity Addition Error")]
-    LiquidityAddValueError,
-    #[msg("Zero Supply Liquidity")]
-    ZeroSupplyLiquidity,
-    #[msg("Slippage Check")]
-    SlippageCheck,
-    #[msg("Invalid Liquidity")]
-    InvalidLiquidity,
-    #[msg("Invalid Token 0 Amount")]
-    ZeroToken0Amount,
-    #[msg("Invalid Token 1 Amount")]
-    ZeroToken1Amount,
-    #[msg("Invalid Vault Account")]
-    InvalidVault,
-    #[msg("Test Invalid Message")]
-    InvalidMessage,
-    #[msg("Invalid Tick Array")]
-    InvalidTickArray
-}
\ No newline at end of file
@@ -1,7 +1,4 @@
 use anchor_lang::prelude::*;
-pub mod errors;
-pub use errors::*;
-
 
 #[error_code]
 pub enum ClmmError {
@@ -26,5 +23,81 @@ pub enum ClmmError {
     #[msg("Test Invalid Message")]
     InvalidMessage,
     #[msg("Invalid Tick Array")]
-    InvalidTickArray
+    InvalidTickArray,
+    #[msg("Not Approved")]
+    NotApproved,
+    #[msg("Invalid Tick Index")]
+    InvalidTickIndex,
+    #[msg("Remove Liquidity Before Closing Position")]
+    ClosePositionErr,
+    #[msg("Invalid Fee Rate")]
+    InvalidFeeRate,
+    #[msg("Invalid Tick Spacing")]
+    InvalidTickSpacing,
+    #[msg("Not Enough Tick Array Accounts")]
+    NotEnoughTickArrayAccount,
+    #[msg("Missing Tick Array Bitmap Extension")]
+    MissingTickArrayBitmapExtension,
+    #[msg("Invalid Tick Array Bitmap Extension")]
+    InvalidTickArrayBitmapExtension,
+    #[msg("Tick Array Still Has Initialized Ticks")]
+    TickArrayNotEmpty,
+    #[msg("Mint Has Unsupported Permanent Delegate")]
+    UnsupportedMintPermanentDelegate,
+    #[msg("Mint Is Non Transferable")]
+    UnsupportedMintNonTransferable,
+    #[msg("Mint Accounts Default To Frozen")]
+    UnsupportedMintDefaultFrozen,
+    #[msg("Mint Has Unsupported Transfer Hook")]
+    UnsupportedMintTransferHook,
+    #[msg("Mint Has Unsupported Extension")]
+    UnsupportedMintExtension,
+    #[msg("Invalid Amm Config")]
+    InvalidAmmConfig,
+    #[msg("Invalid Observation State")]
+    InvalidObservationState,
+    #[msg("Observation Too Old")]
+    ObservationTooOld,
+    #[msg("Invalid TWAP Window")]
+    InvalidTwapWindow,
+    #[msg("Tick Not Initialized")]
+    TickNotInitialized,
+    #[msg("All Reward Slots Are In Use")]
+    FullRewardInfo,
+    #[msg("Reward Token Already In Use")]
+    RewardTokenAlreadyInUse,
+    #[msg("Invalid Reward Index")]
+    InvalidRewardIndex,
+    #[msg("Invalid Reward Params")]
+    InvalidRewardParams,
+    #[msg("Invalid Reward Accounts")]
+    InvalidRewardAccounts,
+    #[msg("Signer Is Not The Reward Authority")]
+    InvalidRewardAuthority,
+    #[msg("Signer Is Not The Admin")]
+    NotAdmin,
+    #[msg("Signer Is Not The Pending Admin")]
+    NotPendingAdmin,
+    #[msg("Open Position Is Disabled For This Pool")]
+    OpenPositionDisabled,
+    #[msg("Increase Liquidity Is Disabled For This Pool")]
+    IncreaseLiquidityDisabled,
+    #[msg("Decrease Liquidity Is Disabled For This Pool")]
+    DecreaseLiquidityDisabled,
+    #[msg("Swap Is Disabled For This Pool")]
+    SwapDisabled,
+    #[msg("Collect Is Disabled For This Pool")]
+    CollectDisabled,
+    #[msg("Calculate Overflow")]
+    CalculateOverflow,
+    #[msg("Invalid Token Mint")]
+    InvalidTokenMint,
+    #[msg("Swap Amount Is Zero")]
+    ZeroAmountSpecified,
+    #[msg("Invalid Pool Status")]
+    InvalidPoolStatus,
+    #[msg("Reward Has Not Ended")]
+    RewardNotEnded,
+    #[msg("Too Many Tick Arrays")]
+    TooManyTickArrays
 }
\ No newline at end of file
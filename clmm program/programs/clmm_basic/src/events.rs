@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+/// Emits an event through the program log or, when built with the `event-cpi`
+/// feature, through a self-CPI that survives log truncation. The instruction's
+/// accounts must carry `#[cfg_attr(feature = "event-cpi", event_cpi)]`
+#[macro_export]
+macro_rules! emit_event {
+    ($ctx:ident, $event:expr) => {
+        #[cfg(feature = "event-cpi")]
+        {
+            let ctx = &$ctx;
+            anchor_lang::prelude::emit_cpi!($event);
+        }
+        #[cfg(not(feature = "event-cpi"))]
+        anchor_lang::prelude::emit!($event);
+    };
+}
+
+#[event]
+pub struct PoolCreatedEvent {
+    pub pool_state: Pubkey,
+    pub pool_creator: Pubkey,
+    pub amm_config: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub tick_spacing: u16,
+    pub sqrt_price_x64: u128,
+    pub tick: i32,
+}
+
+/// Emitted whenever a position's liquidity is added or burned
+#[event]
+pub struct LiquidityChangeEvent {
+    pub pool_state: Pubkey,
+    pub position: Pubkey,
+    pub sender: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// Positive when liquidity is added, negative when it is burned
+    pub liquidity_delta: i128,
+    /// Token amounts entering or leaving the vaults
+    pub amount_0: u64,
+    pub amount_1: u64,
+    /// Liquidity changes never move the price, so these hold before and after
+    pub sqrt_price_x64: u128,
+    pub tick: i32,
+    /// Pool liquidity in range before and after the change
+    pub liquidity_before: u128,
+    pub liquidity_after: u128,
+}
+
+#[event]
+pub struct SwapEvent {
+    pub pool_state: Pubkey,
+    pub sender: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_token_account: Pubkey,
+    pub zero_for_one: bool,
+    /// Token amounts entering and leaving the vaults
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub sqrt_price_x64_before: u128,
+    pub sqrt_price_x64_after: u128,
+    pub tick_before: i32,
+    pub tick_after: i32,
+    pub liquidity_before: u128,
+    pub liquidity_after: u128,
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(address = global_config.pending_admin @ ClmmError::NotPendingAdmin)]
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+/// Completes an admin transfer started by transfer_admin
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let global_config = &mut ctx.accounts.global_config;
+    global_config.admin = ctx.accounts.pending_admin.key();
+    global_config.pending_admin = Pubkey::default();
+    Ok(())
+}
@@ -3,13 +3,15 @@ use crate::libraries::liquidity_math::add_delta;
 use crate::libraries::liquidity_math::get_delta_amounts_signed;
 use crate::states::tick_array::TickArrayState;
 use crate::states::tick_array::TickState;
-use crate::states::*;
+use crate::states::tick_array::{get_fee_growth_inside, get_reward_growths_inside};
+use crate::states::tick_array_bitmap_extension::flip_tick_array_bits;
+use crate::states::oracle::{load_pool_observations_mut, Observation, ObservationState};
 use crate::util::account_load::AccountLoad;
-use crate::util::token::transfer_from_user_to_pool_vault;
-use crate::PoolState;
-use anchor_lang::{prelude::*, system_program};
-use anchor_spl::token::{Mint, Token, TokenAccount};
-use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, Token2022};
+use crate::util::token::{get_transfer_inverse_fee, transfer_from_user_to_pool_vault};
+use crate::{PoolState, REWARD_NUM};
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
 use anchor_spl::token_interface;
 use std::cell::RefMut;
 
@@ -19,6 +21,12 @@ pub struct LiquidityChangeResult {
     pub amount_1: u64,
     pub tick_lower_flipped: bool,
     pub tick_upper_flipped: bool,
+    pub fee_growth_inside_0_x64: u128,
+    pub fee_growth_inside_1_x64: u128,
+    pub reward_growths_inside_x64: [u128; REWARD_NUM],
+    /// Pool liquidity in range before and after the change
+    pub liquidity_before: u128,
+    pub liquidity_after: u128,
 }
 
 /// Add liquidity to an initialized pool
@@ -30,6 +38,8 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
     token_vault_1: &'b AccountInfo<'info>,
     tick_array_lower_loader: &'b AccountLoad<'info, TickArrayState>,
     tick_array_upper_loader: &'b AccountLoad<'info, TickArrayState>,
+    observation_state_loader: &'b AccountLoader<'info, ObservationState>,
+    tick_array_bitmap_extension: Option<&'c AccountInfo<'info>>,
     token_program_2022: Option<&Program<'info, Token2022>>,
     token_program: &'b Program<'info, Token>,
     vault_0_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
@@ -46,7 +56,6 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
     require!(amount_0_max > 0, ClmmError::ZeroToken0Amount);
     require!(amount_1_max > 0, ClmmError::ZeroToken1Amount);
 
-    let liquidity_before = pool_state.liquidity;
     // require_keys_eq!(tick_array_lower_loader.load()?.pool_id, pool_state.key());
     // require_keys_eq!(tick_array_upper_loader.load()?.pool_id, pool_state.key());
 
@@ -65,10 +74,15 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
     if tick_upper_state.tick == 0 {
         tick_upper_state.tick = tick_upper_index;
     }
+    let pool_id = tick_array_lower_loader.load_mut()?.pool_id;
+    let (mut observation_state, mut observations) =
+        load_pool_observations_mut(observation_state_loader.as_ref(), &pool_id)?;
     let clock = Clock::get()?;
-    let mut result = modify_position(
+    let result = modify_position(
         i128::try_from(*liquidity).unwrap(),
         pool_state,
+        &mut observation_state,
+        &mut observations,
         &mut tick_lower_state,
         &mut tick_upper_state,
         clock.unix_timestamp as u64,
@@ -87,38 +101,53 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
         tick_upper_state,
     )?;
 
+    let mut flipped_tick_arrays = Vec::new();
     if result.tick_lower_flipped {
-        tick_array_lower_loader
-            .load_mut()?
-            .update_initialized_tick_count(true)?;
+        let mut tick_array_lower = tick_array_lower_loader.load_mut()?;
+        if tick_array_lower.update_initialized_tick_count(true)? {
+            flipped_tick_arrays.push(tick_array_lower.start_tick_index);
+        }
     }
 
     if result.tick_upper_flipped {
-        tick_array_upper_loader
-            .load_mut()?
-            .update_initialized_tick_count(true)?;
+        let mut tick_array_upper = tick_array_upper_loader.load_mut()?;
+        if tick_array_upper.update_initialized_tick_count(true)? {
+            flipped_tick_arrays.push(tick_array_upper.start_tick_index);
+        }
     }
 
-    let amount_0 = result.amount_0;
-    let amount_1 = result.amount_1;
+    flip_tick_array_bits(
+        pool_state,
+        tick_array_bitmap_extension,
+        pool_id,
+        &flipped_tick_arrays,
+    )?;
+
+    require!(result.amount_0 > 0 || result.amount_1 > 0, ClmmError::ZeroSupplyLiquidity);
 
-    require!(amount_0 > 0 || amount_1 > 0, ClmmError::ZeroSupplyLiquidity);
+    // The vaults must receive the full amounts, so the payer also covers any transfer fee
+    let amount_0 = result
+        .amount_0
+        .checked_add(get_transfer_inverse_fee(vault_0_mint.as_deref(), result.amount_0)?)
+        .ok_or(ClmmError::CalculateOverflow)?;
+    let amount_1 = result
+        .amount_1
+        .checked_add(get_transfer_inverse_fee(vault_1_mint.as_deref(), result.amount_1)?)
+        .ok_or(ClmmError::CalculateOverflow)?;
 
     require_gte!(amount_0_max, amount_0, ClmmError::SlippageCheck);
 
     require_gte!(amount_1_max, amount_1, ClmmError::SlippageCheck);
 
-    let mut token_2022_program_opt: Option<AccountInfo> = None;
-    if token_program_2022.is_some() {
-        token_2022_program_opt = Some(token_program_2022.clone().unwrap().to_account_info());
-    }
+    let token_2022_program_opt: Option<AccountInfo> =
+        token_program_2022.map(|token_program_2022| token_program_2022.to_account_info());
 
     transfer_from_user_to_pool_vault(
         payer,
         token_account_0,
         token_vault_0,
         vault_0_mint,
-        &token_program,
+        token_program,
         token_2022_program_opt.clone(),
         amount_0,
     )?;
@@ -128,7 +157,7 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
         token_account_1,
         token_vault_1,
         vault_1_mint,
-        &token_program,
+        token_program,
         token_2022_program_opt.clone(),
         amount_1,
     )?;
@@ -139,20 +168,67 @@ pub fn add_liquidity<'b, 'c: 'info, 'info>(
 pub fn modify_position(
     liquidity_delta: i128,
     pool_state: &mut RefMut<PoolState>,
+    observation_state: &mut ObservationState,
+    observations: &mut [Observation],
     tick_lower_state: &mut TickState,
     tick_upper_state: &mut TickState,
     timestamp: u64,
 ) -> Result<LiquidityChangeResult> {
     let mut flipped_lower = false;
     let mut flipped_upper = false;
+    let liquidity_before = pool_state.liquidity;
+    let block_timestamp = timestamp as u32;
+    let (tick_cumulative, seconds_per_liquidity_cumulative_x64) = observation_state.observe_single(
+        observations,
+        block_timestamp,
+        0,
+        pool_state.current_tick,
+        pool_state.liquidity,
+    )?;
+    let reward_growths_global_x64 = pool_state.update_reward_infos(timestamp)?;
 
     // update the ticks if liquidity delta is non-zero
     if liquidity_delta != 0 {
         // Update tick state and find if tick is flipped
-        flipped_lower = tick_lower_state.update(pool_state.current_tick, liquidity_delta, false)?;
-        flipped_upper = tick_upper_state.update(pool_state.current_tick, liquidity_delta, true)?;
+        flipped_lower = tick_lower_state.update(
+            pool_state.current_tick,
+            liquidity_delta,
+            pool_state.fee_growth_global_0_x64,
+            pool_state.fee_growth_global_1_x64,
+            seconds_per_liquidity_cumulative_x64,
+            tick_cumulative,
+            block_timestamp,
+            reward_growths_global_x64,
+            false,
+        )?;
+        flipped_upper = tick_upper_state.update(
+            pool_state.current_tick,
+            liquidity_delta,
+            pool_state.fee_growth_global_0_x64,
+            pool_state.fee_growth_global_1_x64,
+            seconds_per_liquidity_cumulative_x64,
+            tick_cumulative,
+            block_timestamp,
+            reward_growths_global_x64,
+            true,
+        )?;
     }
 
+    // Fee and reward growth inside must be read before unused ticks are cleared
+    let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = get_fee_growth_inside(
+        tick_lower_state,
+        tick_upper_state,
+        pool_state.current_tick,
+        pool_state.fee_growth_global_0_x64,
+        pool_state.fee_growth_global_1_x64,
+    );
+    let reward_growths_inside_x64 = get_reward_growths_inside(
+        tick_lower_state,
+        tick_upper_state,
+        pool_state.current_tick,
+        reward_growths_global_x64,
+    );
+
     // Clear Unused ticks
     if liquidity_delta < 0 {
         if tick_lower_state.liquidity_gross == 0 {
@@ -179,14 +255,26 @@ pub fn modify_position(
         if pool_state.current_tick >= tick_lower_state.tick
             && pool_state.current_tick < tick_upper_state.tick
         {
+            // Close out the observation with the liquidity that held until now
+            observation_state.update(
+                observations,
+                block_timestamp,
+                pool_state.current_tick,
+                pool_state.liquidity,
+            );
             pool_state.liquidity = add_delta(pool_state.liquidity, liquidity_delta)?;
         }
     }
 
     Ok(LiquidityChangeResult {
-        amount_0: amount_0,
-        amount_1: amount_1,
+        amount_0,
+        amount_1,
         tick_lower_flipped: flipped_lower,
         tick_upper_flipped: flipped_upper,
+        fee_growth_inside_0_x64,
+        fee_growth_inside_1_x64,
+        reward_growths_inside_x64,
+        liquidity_before,
+        liquidity_after: pool_state.liquidity,
     })
 }
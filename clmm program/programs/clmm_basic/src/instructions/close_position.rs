@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_lang::error::ErrorCode;
+use crate::errors::ClmmError;
+use crate::states::personal_position::{PersonalPositionState, PositionKind, POSITION_SEED};
+use crate::util::position_authority::require_position_authority;
+use anchor_spl::metadata::{self, BurnNft, Metadata};
+use anchor_spl::token::{Mint, Token};
+use mpl_core::instructions::BurnV1CpiBuilder;
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    /// Owner of the position NFT or Core asset, receives the reclaimed rent
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+
+    /// CHECK: Token account holding the position NFT, or the position's mpl-core asset,
+    /// validated in require_position_authority
+    #[account(mut)]
+    pub nft_account: UncheckedAccount<'info>,
+
+    /// Mint of the position NFT, only required for SPL NFT positions
+    #[account(
+        mut,
+        address = personal_position.nft_mint
+    )]
+    pub position_nft_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// CHECK: Metaplex metadata PDA of the position NFT, checked by the metadata program.
+    /// Only required for SPL NFT positions
+    #[account(mut)]
+    pub metadata_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Metaplex master edition PDA of the position NFT, checked by the metadata
+    /// program. Only required for SPL NFT positions
+    #[account(mut)]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            personal_position.nft_mint.as_ref(),
+        ],
+        bump = personal_position.bump,
+        close = nft_owner
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Metaplex token metadata program, only required for SPL NFT positions
+    pub metadata_program: Option<Program<'info, Metadata>>,
+
+    /// CHECK: Metaplex Core program, only required for Core asset positions
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
+}
+
+pub fn close_position<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, ClosePosition<'info>>,
+) -> Result<()> {
+    let personal_position = &ctx.accounts.personal_position;
+
+    require_position_authority(
+        personal_position,
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.nft_account.to_account_info(),
+    )?;
+
+    require!(
+        personal_position.liquidity == 0
+            && personal_position.tokens_owed_0 == 0
+            && personal_position.tokens_owed_1 == 0
+            && personal_position
+                .reward_infos
+                .iter()
+                .all(|reward_info| reward_info.reward_amount_owed == 0),
+        ClmmError::ClosePositionErr
+    );
+
+    match personal_position.position_kind {
+        PositionKind::SplNft => {
+            let position_nft_mint = ctx
+                .accounts
+                .position_nft_mint
+                .as_ref()
+                .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+            let metadata_account = ctx
+                .accounts
+                .metadata_account
+                .as_ref()
+                .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+            let master_edition = ctx
+                .accounts
+                .master_edition
+                .as_ref()
+                .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+            let metadata_program = ctx
+                .accounts
+                .metadata_program
+                .as_ref()
+                .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+
+            // Burns the token and closes the token account, metadata and master edition,
+            // all of their rent going to the owner
+            metadata::burn_nft(
+                CpiContext::new(
+                    metadata_program.to_account_info(),
+                    BurnNft {
+                        metadata: metadata_account.to_account_info(),
+                        owner: ctx.accounts.nft_owner.to_account_info(),
+                        mint: position_nft_mint.to_account_info(),
+                        token: ctx.accounts.nft_account.to_account_info(),
+                        edition: master_edition.to_account_info(),
+                        spl_token: ctx.accounts.token_program.to_account_info(),
+                    },
+                ),
+                None,
+            )?;
+        }
+        PositionKind::CoreAsset => {
+            let mpl_core_program = ctx
+                .accounts
+                .mpl_core_program
+                .as_ref()
+                .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+
+            let nft_owner_info = ctx.accounts.nft_owner.to_account_info();
+            let system_program_info = ctx.accounts.system_program.to_account_info();
+            BurnV1CpiBuilder::new(&mpl_core_program.to_account_info())
+                .asset(&ctx.accounts.nft_account.to_account_info())
+                .payer(&nft_owner_info)
+                .authority(Some(&nft_owner_info))
+                .system_program(Some(&system_program_info))
+                .invoke()?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::states::tick_array::TickArrayState;
+
+#[derive(Accounts)]
+pub struct CloseTickArray<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Tick array with no initialized ticks left
+    #[account(
+        mut,
+        constraint = tick_array.load()?.pool_id == pool_state.key(),
+        close = rent_payer
+    )]
+    pub tick_array: AccountLoader<'info, TickArrayState>,
+
+    /// CHECK: Receives the rent, must be whoever created the tick array
+    #[account(
+        mut,
+        address = tick_array.load()?.rent_payer
+    )]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+pub fn close_tick_array(ctx: Context<CloseTickArray>) -> Result<()> {
+    require_eq!(
+        ctx.accounts.tick_array.load()?.initialized_tick_count,
+        0,
+        ClmmError::TickArrayNotEmpty
+    );
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+use crate::{PoolState, PoolStatusBitIndex};
+use crate::errors::ClmmError;
+use crate::states::personal_position::PersonalPositionState;
+use crate::states::tick_array::{get_fee_growth_inside, TickArrayState};
+use crate::util::position_authority::require_position_authority;
+use crate::util::token::transfer_from_pool_vault_to_user;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    /// Owner of the position NFT or Core asset
+    pub nft_owner: Signer<'info>,
+
+    /// CHECK: Token account holding the position NFT, or the position's mpl-core asset,
+    /// validated in require_position_authority
+    pub nft_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = personal_position.pool_id == pool_state.key()
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    /// Token_0 vault
+    #[account(
+        mut,
+        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
+    )]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token_1 vault
+    #[account(
+        mut,
+        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
+    )]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_0.mint
+    )]
+    pub recipient_token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_1.mint
+    )]
+    pub recipient_token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+pub fn collect_fees<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CollectFees<'info>>,
+    amount_0_requested: u64,
+    amount_1_requested: u64,
+) -> Result<()> {
+    require_position_authority(
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.nft_account.to_account_info(),
+    )?;
+    require!(
+        ctx.accounts
+            .pool_state
+            .load()?
+            .get_status_by_bit(PoolStatusBitIndex::Collect),
+        ClmmError::CollectDisabled
+    );
+
+    let personal_position = &mut ctx.accounts.personal_position;
+    settle_fees(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        personal_position,
+    )?;
+
+    let amount_0 = amount_0_requested.min(personal_position.tokens_owed_0);
+    let amount_1 = amount_1_requested.min(personal_position.tokens_owed_1);
+    personal_position.tokens_owed_0 -= amount_0;
+    personal_position.tokens_owed_1 -= amount_1;
+
+    transfer_from_pool_vault_to_user(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.token_vault_0.to_account_info(),
+        &ctx.accounts.recipient_token_account_0.to_account_info(),
+        Some(ctx.accounts.vault_0_mint.clone()),
+        &ctx.accounts.token_program,
+        Some(ctx.accounts.token_program_2022.to_account_info()),
+        amount_0,
+    )?;
+
+    transfer_from_pool_vault_to_user(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.token_vault_1.to_account_info(),
+        &ctx.accounts.recipient_token_account_1.to_account_info(),
+        Some(ctx.accounts.vault_1_mint.clone()),
+        &ctx.accounts.token_program,
+        Some(ctx.accounts.token_program_2022.to_account_info()),
+        amount_1,
+    )?;
+
+    Ok(())
+}
+
+/// Accrues the fees earned by the position since its last update into tokens_owed
+pub fn settle_fees<'info>(
+    pool_state_loader: &AccountLoader<'info, PoolState>,
+    tick_array_lower_loader: &AccountLoader<'info, TickArrayState>,
+    tick_array_upper_loader: &AccountLoader<'info, TickArrayState>,
+    personal_position: &mut Account<'info, PersonalPositionState>,
+) -> Result<()> {
+    let pool_state = pool_state_loader.load()?;
+
+    let tick_lower_state = *tick_array_lower_loader
+        .load()?
+        .get_tick_state(personal_position.tick_lower_index, pool_state.tick_spacing)?;
+    let tick_upper_state = *tick_array_upper_loader
+        .load()?
+        .get_tick_state(personal_position.tick_upper_index, pool_state.tick_spacing)?;
+
+    let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = get_fee_growth_inside(
+        &tick_lower_state,
+        &tick_upper_state,
+        pool_state.current_tick,
+        pool_state.fee_growth_global_0_x64,
+        pool_state.fee_growth_global_1_x64,
+    );
+
+    personal_position.update_fees(fee_growth_inside_0_x64, fee_growth_inside_1_x64)
+}
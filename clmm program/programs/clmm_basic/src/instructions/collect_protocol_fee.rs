@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+use crate::util::token::transfer_from_pool_vault_to_user;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+#[derive(Accounts)]
+pub struct CollectProtocolFee<'info> {
+    /// Only the program admin can collect protocol fees
+    #[account(address = global_config.admin @ ClmmError::NotAdmin)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Token_0 vault
+    #[account(
+        mut,
+        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
+    )]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token_1 vault
+    #[account(
+        mut,
+        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
+    )]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_0.mint
+    )]
+    pub recipient_token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_1.mint
+    )]
+    pub recipient_token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+pub fn collect_protocol_fee(
+    ctx: Context<CollectProtocolFee>,
+    amount_0_requested: u64,
+    amount_1_requested: u64,
+) -> Result<()> {
+    let amount_0;
+    let amount_1;
+    {
+        let pool_state = &mut ctx.accounts.pool_state.load_mut()?;
+
+        amount_0 = amount_0_requested.min(pool_state.protocol_fees_token_0);
+        amount_1 = amount_1_requested.min(pool_state.protocol_fees_token_1);
+
+        pool_state.protocol_fees_token_0 -= amount_0;
+        pool_state.protocol_fees_token_1 -= amount_1;
+    }
+
+    transfer_from_pool_vault_to_user(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.token_vault_0.to_account_info(),
+        &ctx.accounts.recipient_token_account_0.to_account_info(),
+        Some(ctx.accounts.vault_0_mint.clone()),
+        &ctx.accounts.token_program,
+        Some(ctx.accounts.token_program_2022.to_account_info()),
+        amount_0,
+    )?;
+
+    transfer_from_pool_vault_to_user(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.token_vault_1.to_account_info(),
+        &ctx.accounts.recipient_token_account_1.to_account_info(),
+        Some(ctx.accounts.vault_1_mint.clone()),
+        &ctx.accounts.token_program,
+        Some(ctx.accounts.token_program_2022.to_account_info()),
+        amount_1,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::states::personal_position::PersonalPositionState;
+use crate::states::tick_array::{get_reward_growths_inside, TickArrayState};
+use crate::util::position_authority::require_position_authority;
+use crate::util::token::transfer_from_pool_vault_to_user;
+use crate::{PoolState, PoolStatusBitIndex, RewardInfo, REWARD_NUM};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct CollectRewards<'info> {
+    /// Owner of the position NFT or Core asset
+    pub nft_owner: Signer<'info>,
+
+    /// CHECK: Token account holding the position NFT, or the position's mpl-core asset,
+    /// validated in require_position_authority
+    pub nft_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = personal_position.pool_id == pool_state.key()
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+    // remaining accounts, for each initialized reward in order:
+    // reward_token_vault, recipient_token_account, reward_token_mint
+}
+
+/// Pays out every reward the position has earned
+pub fn collect_rewards<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CollectRewards<'info>>,
+) -> Result<()> {
+    require_position_authority(
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.nft_account.to_account_info(),
+    )?;
+    require!(
+        ctx.accounts
+            .pool_state
+            .load()?
+            .get_status_by_bit(PoolStatusBitIndex::Collect),
+        ClmmError::CollectDisabled
+    );
+
+    let personal_position = &mut ctx.accounts.personal_position;
+    let reward_infos = settle_rewards(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        personal_position,
+    )?;
+
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
+    for (reward_index, reward_info) in reward_infos.iter().enumerate() {
+        if !reward_info.initialized() {
+            continue;
+        }
+        let (Some(reward_token_vault), Some(recipient_token_account), Some(reward_token_mint)) = (
+            remaining_accounts.next(),
+            remaining_accounts.next(),
+            remaining_accounts.next(),
+        ) else {
+            return err!(ClmmError::InvalidRewardAccounts);
+        };
+        require_keys_eq!(
+            reward_token_vault.key(),
+            reward_info.token_vault,
+            ClmmError::InvalidRewardAccounts
+        );
+        require_keys_eq!(
+            reward_token_mint.key(),
+            reward_info.token_mint,
+            ClmmError::InvalidRewardAccounts
+        );
+
+        let amount = personal_position.reward_infos[reward_index].reward_amount_owed;
+        if amount == 0 {
+            continue;
+        }
+        personal_position.reward_infos[reward_index].reward_amount_owed = 0;
+        {
+            let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+            let reward_claimed = pool_state.reward_infos[reward_index].reward_claimed;
+            pool_state.reward_infos[reward_index].reward_claimed =
+                reward_claimed.checked_add(amount).ok_or(ClmmError::CalculateOverflow)?;
+        }
+
+        transfer_from_pool_vault_to_user(
+            &ctx.accounts.pool_state,
+            reward_token_vault,
+            recipient_token_account,
+            Some(Box::new(InterfaceAccount::<Mint>::try_from(reward_token_mint)?)),
+            &ctx.accounts.token_program,
+            Some(ctx.accounts.token_program_2022.to_account_info()),
+            amount,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Accrues the rewards earned by the position since its last update into
+/// reward_amount_owed and returns the pool's updated rewards
+pub fn settle_rewards<'info>(
+    pool_state_loader: &AccountLoader<'info, PoolState>,
+    tick_array_lower_loader: &AccountLoader<'info, TickArrayState>,
+    tick_array_upper_loader: &AccountLoader<'info, TickArrayState>,
+    personal_position: &mut Account<'info, PersonalPositionState>,
+) -> Result<[RewardInfo; REWARD_NUM]> {
+    let mut pool_state = pool_state_loader.load_mut()?;
+    let reward_growths_global_x64 =
+        pool_state.update_reward_infos(Clock::get()?.unix_timestamp as u64)?;
+
+    let tick_lower_state = *tick_array_lower_loader
+        .load()?
+        .get_tick_state(personal_position.tick_lower_index, pool_state.tick_spacing)?;
+    let tick_upper_state = *tick_array_upper_loader
+        .load()?
+        .get_tick_state(personal_position.tick_upper_index, pool_state.tick_spacing)?;
+
+    let reward_growths_inside_x64 = get_reward_growths_inside(
+        &tick_lower_state,
+        &tick_upper_state,
+        pool_state.current_tick,
+        reward_growths_global_x64,
+    );
+    personal_position.update_rewards(reward_growths_inside_x64)?;
+
+    Ok(pool_state.reward_infos)
+}
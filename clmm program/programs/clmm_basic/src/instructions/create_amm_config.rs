@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::libraries::swap_math::FEE_RATE_DENOMINATOR_VALUE;
+use crate::states::config::{AmmConfig, AMM_CONFIG_SEED};
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+
+#[derive(Accounts)]
+#[instruction(index: u16)]
+pub struct CreateAmmConfig<'info> {
+    /// Only the program admin can create fee tiers
+    #[account(
+        mut,
+        address = global_config.admin @ ClmmError::NotAdmin
+    )]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(
+        init,
+        seeds = [
+            AMM_CONFIG_SEED.as_bytes(),
+            &index.to_be_bytes(),
+        ],
+        bump,
+        payer = owner,
+        space = AmmConfig::LEN
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_amm_config(
+    ctx: Context<CreateAmmConfig>,
+    index: u16,
+    tick_spacing: u16,
+    trade_fee_rate: u32,
+    protocol_fee_rate: u32,
+) -> Result<()> {
+    require_gt!(tick_spacing, 0, ClmmError::InvalidTickSpacing);
+    require_gt!(FEE_RATE_DENOMINATOR_VALUE, trade_fee_rate, ClmmError::InvalidFeeRate);
+    require_gte!(FEE_RATE_DENOMINATOR_VALUE, protocol_fee_rate, ClmmError::InvalidFeeRate);
+
+    let amm_config = &mut ctx.accounts.amm_config;
+    amm_config.bump = ctx.bumps.amm_config;
+    amm_config.index = index;
+    amm_config.owner = ctx.accounts.owner.key();
+    amm_config.tick_spacing = tick_spacing;
+    amm_config.trade_fee_rate = trade_fee_rate;
+    amm_config.protocol_fee_rate = protocol_fee_rate;
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+
+#[derive(Accounts)]
+pub struct CreateGlobalConfig<'info> {
+    /// The bootstrap admin key, becomes the first admin of the config
+    #[account(
+        mut,
+        address = crate::admin::id()
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump,
+        payer = admin,
+        space = GlobalConfig::LEN
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_global_config(ctx: Context<CreateGlobalConfig>) -> Result<()> {
+    let global_config = &mut ctx.accounts.global_config;
+    global_config.bump = ctx.bumps.global_config;
+    global_config.admin = ctx.accounts.admin.key();
+    global_config.pending_admin = Pubkey::default();
+    Ok(())
+}
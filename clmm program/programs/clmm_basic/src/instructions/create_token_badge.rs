@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+use crate::states::token_badge::{TokenBadge, TOKEN_BADGE_SEED};
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct CreateTokenBadge<'info> {
+    /// Only the program admin can allow mints
+    #[account(
+        mut,
+        address = global_config.admin @ ClmmError::NotAdmin
+    )]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        seeds = [
+            TOKEN_BADGE_SEED.as_bytes(),
+            token_mint.key().as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = TokenBadge::LEN
+    )]
+    pub token_badge: Account<'info, TokenBadge>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_token_badge(ctx: Context<CreateTokenBadge>) -> Result<()> {
+    ctx.accounts.token_badge.mint = ctx.accounts.token_mint.key();
+    Ok(())
+}
@@ -1,19 +1,52 @@
-use anchor_lang::{prelude::*, system_program};
+use anchor_lang::prelude::*;
 use std::cell::RefMut;
-use crate::PoolState;
+use crate::{PoolState, PoolStatusBitIndex};
 use crate::errors::ClmmError;
 use crate::instructions::add_liquidity::{LiquidityChangeResult, modify_position};
 use crate::states::tick_array::TickArrayState;
-use crate::util::token::transfer_from_pool_vault_to_user;
+use crate::states::oracle::{load_pool_observations_mut, ObservationState};
+use crate::states::tick_array_bitmap_extension::flip_tick_array_bits;
+use crate::states::personal_position::PersonalPositionState;
+use crate::util::token::{get_transfer_fee, transfer_from_pool_vault_to_user};
+use crate::instructions::open_position_core::update_position_asset_liquidity;
+use crate::util::position_authority::require_position_authority;
+use crate::emit_event;
+use crate::events::LiquidityChangeEvent;
 use anchor_spl::token_interface;
 use anchor_spl::token::{Token, TokenAccount};
-use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, Token2022};
+use anchor_spl::token_2022::Token2022;
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
+#[instruction(
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+)]
 pub struct DecreaseLiquidity<'info> {
+    /// Owner of the position NFT or Core asset, pays to resize a Core asset's attributes
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+
     #[account(mut)]
     pub pool_state: AccountLoader<'info, PoolState>,
 
+    /// CHECK: Token account holding the position NFT, or the position's mpl-core asset,
+    /// validated in require_position_authority
+    #[account(mut)]
+    pub nft_account: UncheckedAccount<'info>,
+
+    /// Position being debited, the signer must own its NFT or Core asset
+    #[account(
+        mut,
+        constraint = personal_position.pool_id == pool_state.key(),
+        constraint = personal_position.tick_lower_index == tick_lower_index @ ClmmError::InvalidTickIndex,
+        constraint = personal_position.tick_upper_index == tick_upper_index @ ClmmError::InvalidTickIndex,
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
     /// Token_0 vault
     #[account(
         mut,
@@ -40,6 +73,10 @@ pub struct DecreaseLiquidity<'info> {
     )]
     pub tick_array_upper: AccountLoader<'info, TickArrayState>,
 
+    /// Oracle of the pool, written when in-range liquidity changes
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
     #[account(
         mut,
         token::mint = token_vault_0.mint
@@ -53,14 +90,74 @@ pub struct DecreaseLiquidity<'info> {
     pub recipient_token_account_1: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Metaplex Core program, only required for Core asset positions
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
+}
+
+pub fn decrease_liquidity_v1<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidity<'info>>,
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> Result<()> {
+    require_position_authority(
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.nft_account.to_account_info(),
+    )?;
+
+    let liquidity_change_event = decrease_liquidity(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.pool_state,
+        &mut ctx.accounts.personal_position,
+        &ctx.accounts.token_vault_0.to_account_info(),
+        &ctx.accounts.token_vault_1.to_account_info(),
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &ctx.accounts.observation_state,
+        ctx.remaining_accounts.first(),
+        &ctx.accounts.recipient_token_account_0.to_account_info(),
+        &ctx.accounts.recipient_token_account_1.to_account_info(),
+        &ctx.accounts.token_program,
+        None,
+        None,
+        None,
+        liquidity,
+        amount_0_min,
+        amount_1_min,
+        tick_lower_index,
+        tick_upper_index,
+    )?;
+
+    update_position_asset_liquidity(
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.pool_state,
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_account.to_account_info(),
+        &ctx.accounts.system_program,
+        ctx.accounts.mpl_core_program.as_ref(),
+    )?;
+
+    emit_event!(ctx, liquidity_change_event);
+    Ok(())
 }
 
 pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
+    nft_owner: &'b Signer<'info>,
     pool_state_loader: &'b AccountLoader<'info, PoolState>,
+    personal_position: &mut Account<'info, PersonalPositionState>,
     token_vault_0: &'b AccountInfo<'info>,
     token_vault_1: &'b AccountInfo<'info>,
     tick_array_lower_loader: &'b AccountLoader<'info, TickArrayState>,
     tick_array_upper_loader: &'b AccountLoader<'info, TickArrayState>,
+    observation_state_loader: &'b AccountLoader<'info, ObservationState>,
+    tick_array_bitmap_extension: Option<&'c AccountInfo<'info>>,
     recipient_token_account_0: &'b AccountInfo<'info>,
     recipient_token_account_1: &'b AccountInfo<'info>,
     token_program: &'b Program<'info, Token>,
@@ -72,46 +169,78 @@ pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
     amount_1_min: u64,
     tick_lower_index: i32,
     tick_upper_index: i32,
-) -> Result<()> {
-    let (amount_0, amount_1) = {  
+) -> Result<LiquidityChangeEvent> {
+    require_gte!(personal_position.liquidity, liquidity, ClmmError::InvalidLiquidity);
+
+    let liquidity_change_event = {  
         let mut pool_state = pool_state_loader.load_mut()?;
+        require!(
+            pool_state.get_status_by_bit(PoolStatusBitIndex::DecreaseLiquidity),
+            ClmmError::DecreaseLiquidityDisabled
+        );
     
         let LiquidityChangeResult {
             amount_0,
             amount_1,
+            fee_growth_inside_0_x64,
+            fee_growth_inside_1_x64,
+            reward_growths_inside_x64,
+            liquidity_before,
+            liquidity_after,
             ..
         } = burn_liquidity(
             pool_state_loader,
             &mut pool_state,
             tick_array_lower_loader,
             tick_array_upper_loader,
+            observation_state_loader,
+            tick_array_bitmap_extension,
             tick_lower_index,
             tick_upper_index,
             liquidity,
         )?;
-        (amount_0, amount_1)
+        personal_position.update_fees(fee_growth_inside_0_x64, fee_growth_inside_1_x64)?;
+        personal_position.update_rewards(reward_growths_inside_x64)?;
+        personal_position.update_liquidity(-i128::try_from(liquidity).unwrap())?;
+        LiquidityChangeEvent {
+            pool_state: pool_state_loader.key(),
+            position: personal_position.key(),
+            sender: nft_owner.key(),
+            tick_lower: tick_lower_index,
+            tick_upper: tick_upper_index,
+            liquidity_delta: -i128::try_from(liquidity).unwrap(),
+            amount_0,
+            amount_1,
+            sqrt_price_x64: pool_state.sqrt_price_x64,
+            tick: pool_state.current_tick,
+            liquidity_before,
+            liquidity_after,
+        }
     };
-    let decrease_amount_0 = amount_0;
-    let decrease_amount_1 = amount_1;
+    let decrease_amount_0 = liquidity_change_event.amount_0;
+    let decrease_amount_1 = liquidity_change_event.amount_1;
 
     if liquidity > 0 {
+        // Slippage applies to what the recipient gets after any transfer fee
         require_gte!(
-            decrease_amount_0,
+            decrease_amount_0
+                .checked_sub(get_transfer_fee(vault_0_mint.as_deref(), decrease_amount_0)?)
+                .ok_or(ClmmError::CalculateOverflow)?,
             amount_0_min,
             ClmmError::SlippageCheck
         );
         require_gte!(
-            decrease_amount_1,
+            decrease_amount_1
+                .checked_sub(get_transfer_fee(vault_1_mint.as_deref(), decrease_amount_1)?)
+                .ok_or(ClmmError::CalculateOverflow)?,
             amount_1_min,
             ClmmError::SlippageCheck
         );
     }
 
 
-    let mut token_2022_program_opt: Option<AccountInfo> = None;
-    if token_program_2022.is_some() {
-        token_2022_program_opt = Some(token_program_2022.clone().unwrap().to_account_info());
-    }
+    let token_2022_program_opt: Option<AccountInfo> =
+        token_program_2022.map(|token_program_2022| token_program_2022.to_account_info());
 
     transfer_from_pool_vault_to_user(
         pool_state_loader,
@@ -133,7 +262,7 @@ pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
         decrease_amount_1,
     )?;
 
-    Ok(())
+    Ok(liquidity_change_event)
 }
 
 pub fn burn_liquidity<'b, 'c: 'info, 'info> (
@@ -141,13 +270,14 @@ pub fn burn_liquidity<'b, 'c: 'info, 'info> (
     pool_state: &mut RefMut<PoolState>,
     tick_array_lower_loader: &AccountLoader<'info, TickArrayState>,
     tick_array_upper_loader: &AccountLoader<'info, TickArrayState>,
+    observation_state_loader: &AccountLoader<'info, ObservationState>,
+    tick_array_bitmap_extension: Option<&'c AccountInfo<'info>>,
     tick_lower_index: i32,
     tick_upper_index: i32,
     liquidity: u128,
 ) -> Result<LiquidityChangeResult> {
     require_keys_eq!(tick_array_lower_loader.load()?.pool_id, pool_state_loader.key());
     require_keys_eq!(tick_array_upper_loader.load()?.pool_id, pool_state_loader.key());
-    let liquidity_before = pool_state.liquidity;
 
     // get tick_state
     let mut tick_lower_state = *tick_array_lower_loader
@@ -158,10 +288,14 @@ pub fn burn_liquidity<'b, 'c: 'info, 'info> (
         .load_mut()?
         .get_tick_state_mut(tick_upper_index, pool_state.tick_spacing)?;
     
+    let (mut observation_state, mut observations) =
+        load_pool_observations_mut(observation_state_loader.as_ref(), &pool_state_loader.key())?;
     let clock = Clock::get()?;
     let result = modify_position(
         -i128::try_from(liquidity).unwrap(),
         pool_state,
+        &mut observation_state,
+        &mut observations,
         &mut tick_lower_state,
         &mut tick_upper_state,
         clock.unix_timestamp as u64,
@@ -180,15 +314,27 @@ pub fn burn_liquidity<'b, 'c: 'info, 'info> (
         tick_upper_state,
     )?;
 
+    let mut flipped_tick_arrays = Vec::new();
     if result.tick_lower_flipped {
         let mut tick_array_lower = tick_array_lower_loader.load_mut()?;
-        tick_array_lower.update_initialized_tick_count(false)?;
+        if tick_array_lower.update_initialized_tick_count(false)? {
+            flipped_tick_arrays.push(tick_array_lower.start_tick_index);
+        }
     }
 
     if result.tick_upper_flipped {
         let mut tick_array_upper = tick_array_upper_loader.load_mut()?;
-        tick_array_upper.update_initialized_tick_count(false)?;
+        if tick_array_upper.update_initialized_tick_count(false)? {
+            flipped_tick_arrays.push(tick_array_upper.start_tick_index);
+        }
     }
 
+    flip_tick_array_bits(
+        pool_state,
+        tick_array_bitmap_extension,
+        pool_state_loader.key(),
+        &flipped_tick_arrays,
+    )?;
+
     Ok(result)
 }
\ No newline at end of file
@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+use crate::emit_event;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::instructions::decrease_liquidity::decrease_liquidity;
+use crate::states::tick_array::TickArrayState;
+use crate::states::oracle::ObservationState;
+use crate::states::personal_position::PersonalPositionState;
+use crate::instructions::open_position_core::update_position_asset_liquidity;
+use crate::util::position_authority::require_position_authority;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// Same as DecreaseLiquidity, for pools holding SPL Token or Token-2022 mints
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+)]
+pub struct DecreaseLiquidityV2<'info> {
+    /// Owner of the position NFT or Core asset, pays to resize a Core asset's attributes
+    #[account(mut)]
+    pub nft_owner: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK: Token account holding the position NFT, or the position's mpl-core asset,
+    /// validated in require_position_authority
+    #[account(mut)]
+    pub nft_account: UncheckedAccount<'info>,
+
+    /// Position being debited, the signer must own its NFT or Core asset
+    #[account(
+        mut,
+        constraint = personal_position.pool_id == pool_state.key(),
+        constraint = personal_position.tick_lower_index == tick_lower_index @ ClmmError::InvalidTickIndex,
+        constraint = personal_position.tick_upper_index == tick_upper_index @ ClmmError::InvalidTickIndex,
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    /// Token_0 vault
+    #[account(
+        mut,
+        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
+    )]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token_1 vault
+    #[account(
+        mut,
+        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
+    )]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = tick_array_lower.load()?.pool_id == pool_state.key()
+    )]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(
+        mut,
+        constraint = tick_array_upper.load()?.pool_id == pool_state.key()
+    )]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    /// Oracle of the pool, written when in-range liquidity changes
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_0.mint
+    )]
+    pub recipient_token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_1.mint
+    )]
+    pub recipient_token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Metaplex Core program, only required for Core asset positions
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
+}
+
+pub fn decrease_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityV2<'info>>,
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> Result<()> {
+    require_position_authority(
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.nft_account.to_account_info(),
+    )?;
+
+    let liquidity_change_event = decrease_liquidity(
+        &ctx.accounts.nft_owner,
+        &ctx.accounts.pool_state,
+        &mut ctx.accounts.personal_position,
+        &ctx.accounts.token_vault_0.to_account_info(),
+        &ctx.accounts.token_vault_1.to_account_info(),
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &ctx.accounts.observation_state,
+        ctx.remaining_accounts.first(),
+        &ctx.accounts.recipient_token_account_0.to_account_info(),
+        &ctx.accounts.recipient_token_account_1.to_account_info(),
+        &ctx.accounts.token_program,
+        Some(&ctx.accounts.token_program_2022),
+        Some(ctx.accounts.vault_0_mint.clone()),
+        Some(ctx.accounts.vault_1_mint.clone()),
+        liquidity,
+        amount_0_min,
+        amount_1_min,
+        tick_lower_index,
+        tick_upper_index,
+    )?;
+
+    update_position_asset_liquidity(
+        &ctx.accounts.nft_owner.to_account_info(),
+        &ctx.accounts.pool_state,
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_account.to_account_info(),
+        &ctx.accounts.system_program,
+        ctx.accounts.mpl_core_program.as_ref(),
+    )?;
+
+    emit_event!(ctx, liquidity_change_event);
+    Ok(())
+}
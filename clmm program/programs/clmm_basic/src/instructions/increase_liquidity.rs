@@ -1,30 +1,59 @@
-use anchor_lang::{prelude::*, system_program};
-use crate::PoolState;
+use anchor_lang::prelude::*;
+use crate::{PoolState, PoolStatusBitIndex};
 use crate::errors::ClmmError;
-use crate::states::*;
 use crate::instructions::*;
 use crate::util::*;
+use crate::emit_event;
+use crate::events::LiquidityChangeEvent;
 use crate::states::tick_array::TickArrayState;
-use anchor_spl::token_interface;
+use crate::states::oracle::ObservationState;
+use crate::states::personal_position::PersonalPositionState;
 use anchor_spl::token_interface::Mint;
 use anchor_spl::token::{Token, TokenAccount};
-use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, Token2022};
+use anchor_spl::token_2022::Token2022;
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
+#[instruction(
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+)]
 pub struct IncreaseLiquidity<'info> {
 
+    /// Owner of the position NFT or Core asset, pays for the added liquidity
     #[account(mut)]
     pub payer: Signer<'info>,
 
     #[account(mut)]
     pub pool_state: AccountLoader<'info, PoolState>,
 
+    /// CHECK: Token account holding the position NFT, or the position's mpl-core asset,
+    /// validated in require_position_authority
+    #[account(mut)]
+    pub nft_account: UncheckedAccount<'info>,
+
+    /// Position being credited, the signer must own its NFT or Core asset
+    #[account(
+        mut,
+        constraint = personal_position.pool_id == pool_state.key(),
+        constraint = personal_position.tick_lower_index == tick_lower_index @ ClmmError::InvalidTickIndex,
+        constraint = personal_position.tick_upper_index == tick_upper_index @ ClmmError::InvalidTickIndex,
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
     #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
     pub tick_array_lower: AccountLoader<'info, TickArrayState>,
 
     #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
     pub tick_array_upper: AccountLoader<'info, TickArrayState>,
 
+    /// Oracle of the pool, written when in-range liquidity changes
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
     /// Payer's token account for token_0
     #[account(
         mut,
@@ -55,6 +84,12 @@ pub struct IncreaseLiquidity<'info> {
     pub token_vault_1: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Metaplex Core program, only required for Core asset positions
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
 }
 
 pub fn increase_liquidity_v1<'a, 'b, 'c: 'info, 'info>(
@@ -65,11 +100,20 @@ pub fn increase_liquidity_v1<'a, 'b, 'c: 'info, 'info>(
     tick_lower_index: i32,
     tick_upper_index: i32,
 ) -> Result<()> {
-    increase_liquidity(
+    require_position_authority(
+        &ctx.accounts.personal_position,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.nft_account.to_account_info(),
+    )?;
+
+    let liquidity_change_event = increase_liquidity(
         &ctx.accounts.payer,
         &ctx.accounts.pool_state,
         &ctx.accounts.tick_array_lower,
         &ctx.accounts.tick_array_upper,
+        &ctx.accounts.observation_state,
+        ctx.remaining_accounts.first(),
+        &mut ctx.accounts.personal_position,
         &ctx.accounts.token_account_0.to_account_info(),
         &ctx.accounts.token_account_1.to_account_info(),
         &ctx.accounts.token_vault_0.to_account_info(),
@@ -83,7 +127,19 @@ pub fn increase_liquidity_v1<'a, 'b, 'c: 'info, 'info>(
         amount_1_max,
         tick_lower_index,
         tick_upper_index,
-    )
+    )?;
+
+    update_position_asset_liquidity(
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.pool_state,
+        &ctx.accounts.personal_position,
+        &ctx.accounts.nft_account.to_account_info(),
+        &ctx.accounts.system_program,
+        ctx.accounts.mpl_core_program.as_ref(),
+    )?;
+
+    emit_event!(ctx, liquidity_change_event);
+    Ok(())
 }
 
 pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
@@ -91,6 +147,9 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
     pool_state_loader: &'b AccountLoader<'info, PoolState>,
     tick_array_lower_loader: &'b AccountLoader<'info, TickArrayState>,
     tick_array_upper_loader: &'b AccountLoader<'info, TickArrayState>,
+    observation_state_loader: &'b AccountLoader<'info, ObservationState>,
+    tick_array_bitmap_extension: Option<&'c AccountInfo<'info>>,
+    personal_position: &mut Account<'info, PersonalPositionState>,
     token_account_0: &'b AccountInfo<'info>,
     token_account_1: &'b AccountInfo<'info>,
     token_vault_0: &'b AccountInfo<'info>,
@@ -105,16 +164,16 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
     amount_1_max: u64,
     tick_lower_index: i32,
     tick_upper_index: i32,
-) -> Result<()> {
+) -> Result<LiquidityChangeEvent> {
     let mut liquidity = liquidity;
-    {
+    let (result, sqrt_price_x64, tick) = {
     let pool_state = &mut pool_state_loader.load_mut()?;
+    require!(
+        pool_state.get_status_by_bit(PoolStatusBitIndex::IncreaseLiquidity),
+        ClmmError::IncreaseLiquidityDisabled
+    );
 
-    let LiquidityChangeResult {
-        amount_0,
-        amount_1,
-        ..
-    } = add_liquidity(
+    let result = add_liquidity(
         payer,
         token_account_0,
         token_account_1,
@@ -122,6 +181,8 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
         token_vault_1,
         &AccountLoad::<TickArrayState>::try_from(&tick_array_lower_loader.to_account_info())?,
         &AccountLoad::<TickArrayState>::try_from(&tick_array_upper_loader.to_account_info())?,
+        observation_state_loader,
+        tick_array_bitmap_extension,
         token_program_2022,
         token_program,
         vault_0_mint,
@@ -133,7 +194,25 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
         tick_lower_index,
         tick_upper_index,
     )?;
-    }
-
-    Ok(())
+    (result, pool_state.sqrt_price_x64, pool_state.current_tick)
+    };
+
+    personal_position.update_fees(result.fee_growth_inside_0_x64, result.fee_growth_inside_1_x64)?;
+    personal_position.update_rewards(result.reward_growths_inside_x64)?;
+    personal_position.update_liquidity(i128::try_from(liquidity).unwrap())?;
+
+    Ok(LiquidityChangeEvent {
+        pool_state: pool_state_loader.key(),
+        position: personal_position.key(),
+        sender: payer.key(),
+        tick_lower: tick_lower_index,
+        tick_upper: tick_upper_index,
+        liquidity_delta: i128::try_from(liquidity).unwrap(),
+        amount_0: result.amount_0,
+        amount_1: result.amount_1,
+        sqrt_price_x64,
+        tick,
+        liquidity_before: result.liquidity_before,
+        liquidity_after: result.liquidity_after,
+    })
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+use anchor_lang::{prelude::*, system_program};
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::states::oracle::ObservationState;
+
+#[derive(Accounts)]
+pub struct IncreaseObservationCardinalityNext<'info> {
+    /// Pays the rent for the larger account, can be anyone
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        mut,
+        constraint = observation_state.load()?.pool_id == pool_state.key()
+            @ ClmmError::InvalidObservationState
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Reallocs the pool's observation account to hold `observation_cardinality_next`
+/// observations. Swaps start using the new room once the ring buffer wraps around
+/// to it. An account can grow by at most 10 KiB, about 360 observations, per call
+pub fn increase_observation_cardinality_next(
+    ctx: Context<IncreaseObservationCardinalityNext>,
+    observation_cardinality_next: u16,
+) -> Result<()> {
+    let observation_cardinality_next_old =
+        ctx.accounts.observation_state.load()?.observation_cardinality_next;
+    if observation_cardinality_next <= observation_cardinality_next_old {
+        return Ok(());
+    }
+
+    let observation_state_info = ctx.accounts.observation_state.to_account_info();
+    let space = ObservationState::space(observation_cardinality_next);
+    let required_lamports = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(observation_state_info.lamports());
+    if required_lamports > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.payer.to_account_info(),
+            to: observation_state_info.clone(),
+        };
+        let cpi_context = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_context, required_lamports)?;
+    }
+    // The new slots are zeroed, which marks them as not yet written
+    observation_state_info.resize(space)?;
+
+    ctx.accounts.observation_state.load_mut()?.observation_cardinality_next =
+        observation_cardinality_next;
+    Ok(())
+}
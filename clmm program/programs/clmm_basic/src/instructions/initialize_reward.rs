@@ -0,0 +1,170 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+use crate::states::token_badge::{TokenBadge, TOKEN_BADGE_SEED};
+use crate::util::token::{
+    check_mint_extensions, create_token_vault_account, get_transfer_inverse_fee,
+    transfer_from_user_to_pool_vault,
+};
+use crate::{PoolState, RewardInfo, REWARD_VAULT_SEED};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct InitializeReward<'info> {
+    /// Only the program admin decides which pools are incentivized
+    #[account(address = global_config.admin @ ClmmError::NotAdmin)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    /// Funds the reward and becomes its authority
+    #[account(mut)]
+    pub reward_funder: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = reward_token_mint,
+        token::authority = reward_funder,
+    )]
+    pub funder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Reward vault of the pool, initialized in contract
+    #[account(
+        mut,
+        seeds = [
+            REWARD_VAULT_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            reward_token_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub reward_token_vault: UncheckedAccount<'info>,
+
+    /// Allows reward_token_mint regardless of its extensions
+    #[account(
+        seeds = [
+            TOKEN_BADGE_SEED.as_bytes(),
+            reward_token_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub token_badge: Option<Box<Account<'info, TokenBadge>>>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Starts emitting `reward_token_mint` to the pool's in-range liquidity between
+/// open_time and end_time. The funder deposits the whole schedule up front
+pub fn initialize_reward(
+    ctx: Context<InitializeReward>,
+    open_time: u64,
+    end_time: u64,
+    emissions_per_second_x64: u128,
+) -> Result<()> {
+    // Badged mints skip the extension checks
+    if ctx.accounts.token_badge.is_none() {
+        check_mint_extensions(&ctx.accounts.reward_token_mint)?;
+    }
+
+    let curr_timestamp = Clock::get()?.unix_timestamp as u64;
+    require!(
+        open_time >= curr_timestamp && end_time > open_time && emissions_per_second_x64 > 0,
+        ClmmError::InvalidRewardParams
+    );
+
+    create_token_vault_account(
+        &ctx.accounts.reward_funder,
+        &ctx.accounts.pool_state.to_account_info(),
+        &ctx.accounts.reward_token_vault,
+        &ctx.accounts.reward_token_mint,
+        &ctx.accounts.system_program,
+        &ctx.accounts.reward_token_program,
+        &[
+            REWARD_VAULT_SEED.as_bytes(),
+            ctx.accounts.pool_state.key().as_ref(),
+            ctx.accounts.reward_token_mint.key().as_ref(),
+            &[ctx.bumps.reward_token_vault][..],
+        ],
+    )?;
+
+    let required_amount = {
+        let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+        let reward_token_mint = ctx.accounts.reward_token_mint.key();
+        require!(
+            !pool_state
+                .reward_infos
+                .iter()
+                .any(|reward_info| reward_info.token_mint == reward_token_mint),
+            ClmmError::RewardTokenAlreadyInUse
+        );
+        let reward_index = pool_state
+            .reward_infos
+            .iter()
+            .position(|reward_info| !reward_info.initialized())
+            .ok_or(error!(ClmmError::FullRewardInfo))?;
+
+        let reward_info = &mut pool_state.reward_infos[reward_index];
+        *reward_info = RewardInfo {
+            token_mint: reward_token_mint,
+            token_vault: ctx.accounts.reward_token_vault.key(),
+            authority: ctx.accounts.reward_funder.key(),
+            emissions_per_second_x64,
+            open_time,
+            end_time,
+            last_update_time: curr_timestamp,
+            ..Default::default()
+        };
+        reward_info.required_vault_amount(curr_timestamp)?
+    };
+
+    fund_reward_vault(
+        &ctx.accounts.reward_funder,
+        &ctx.accounts.funder_token_account.to_account_info(),
+        &ctx.accounts.reward_token_vault,
+        ctx.accounts.reward_token_mint.clone(),
+        &ctx.accounts.reward_token_program,
+        0,
+        required_amount,
+    )
+}
+
+/// Tops the reward vault up to `required_amount`, the funder also covers any transfer fee
+pub fn fund_reward_vault<'info>(
+    funder: &Signer<'info>,
+    funder_token_account: &AccountInfo<'info>,
+    reward_token_vault: &AccountInfo<'info>,
+    reward_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    reward_token_program: &AccountInfo<'info>,
+    vault_amount: u64,
+    required_amount: u64,
+) -> Result<()> {
+    let shortfall = required_amount.saturating_sub(vault_amount);
+    if shortfall == 0 {
+        return Ok(());
+    }
+    let amount = shortfall
+        .checked_add(get_transfer_inverse_fee(Some(&reward_token_mint), shortfall)?)
+        .ok_or(ClmmError::CalculateOverflow)?;
+
+    transfer_from_user_to_pool_vault(
+        funder,
+        funder_token_account,
+        reward_token_vault,
+        Some(reward_token_mint),
+        reward_token_program,
+        Some(reward_token_program.clone()),
+        amount,
+    )
+}
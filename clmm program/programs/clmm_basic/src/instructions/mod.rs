@@ -1,11 +1,61 @@
 pub mod open_position;
+pub mod open_position_core;
 pub mod add_liquidity;
 pub mod increase_liquidity;
 pub mod decrease_liquidity;
 pub mod swap;
+pub mod close_position;
+pub mod collect_fees;
+pub mod create_amm_config;
+pub mod create_token_badge;
+pub mod collect_protocol_fee;
+pub mod close_tick_array;
+pub mod open_position_v2;
+pub mod increase_liquidity_v2;
+pub mod decrease_liquidity_v2;
+pub mod swap_v2;
+pub mod swap_exact_out;
+pub mod swap_router_base_in;
+pub mod quote_swap;
+pub mod observe;
+pub mod increase_observation_cardinality_next;
+pub mod snapshot_cumulatives_inside;
+pub mod initialize_reward;
+pub mod set_reward_params;
+pub mod withdraw_reward_surplus;
+pub mod collect_rewards;
+pub mod create_global_config;
+pub mod transfer_admin;
+pub mod accept_admin;
+pub mod set_pool_status;
 
 pub use open_position::*;
+pub use open_position_core::*;
 pub use add_liquidity::*;
 pub use increase_liquidity::*;
 pub use decrease_liquidity::*;
-pub use swap::*;
\ No newline at end of file
+pub use swap::*;
+pub use close_position::*;
+pub use collect_fees::*;
+pub use create_amm_config::*;
+pub use create_token_badge::*;
+pub use collect_protocol_fee::*;
+pub use close_tick_array::*;
+pub use open_position_v2::*;
+pub use increase_liquidity_v2::*;
+pub use decrease_liquidity_v2::*;
+pub use swap_v2::*;
+pub use swap_exact_out::*;
+pub use swap_router_base_in::*;
+pub use quote_swap::*;
+pub use observe::*;
+pub use increase_observation_cardinality_next::*;
+pub use snapshot_cumulatives_inside::*;
+pub use initialize_reward::*;
+pub use set_reward_params::*;
+pub use withdraw_reward_surplus::*;
+pub use collect_rewards::*;
+pub use create_global_config::*;
+pub use transfer_admin::*;
+pub use accept_admin::*;
+pub use set_pool_status::*;
\ No newline at end of file
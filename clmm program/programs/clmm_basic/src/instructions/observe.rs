@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::states::oracle::{load_observations, ObservationState};
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        constraint = observation_state.load()?.pool_id == pool_state.key()
+            @ ClmmError::InvalidObservationState
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+}
+
+/// Written to return data by observe
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObserveResult {
+    /// Tick cumulative as of now
+    pub tick_cumulative: i64,
+    /// Time-weighted average tick over each requested window, ending now
+    pub twap_ticks: Vec<i32>,
+}
+
+/// Returns the time-weighted average tick over each of `twap_windows` seconds
+/// through return data
+pub fn observe(ctx: Context<Observe>, twap_windows: Vec<u32>) -> Result<()> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let (observation_state, observations) =
+        load_observations(ctx.accounts.observation_state.as_ref())?;
+    let block_timestamp = Clock::get()?.unix_timestamp as u32;
+    let (tick, liquidity) = (pool_state.current_tick, pool_state.liquidity);
+
+    let (tick_cumulative, _) =
+        observation_state.observe_single(&observations, block_timestamp, 0, tick, liquidity)?;
+    let mut twap_ticks = Vec::with_capacity(twap_windows.len());
+    for twap_window in twap_windows {
+        require!(twap_window > 0, ClmmError::InvalidTwapWindow);
+        let (tick_cumulative_start, _) =
+            observation_state.observe_single(&observations, block_timestamp, twap_window, tick, liquidity)?;
+        let tick_cumulative_delta = tick_cumulative.wrapping_sub(tick_cumulative_start);
+        // Round toward negative infinity
+        let mut twap_tick = tick_cumulative_delta / i64::from(twap_window);
+        if tick_cumulative_delta < 0 && tick_cumulative_delta % i64::from(twap_window) != 0 {
+            twap_tick -= 1;
+        }
+        twap_ticks.push(twap_tick as i32);
+    }
+
+    set_return_data(
+        &ObserveResult {
+            tick_cumulative,
+            twap_ticks,
+        }
+        .try_to_vec()?,
+    );
+    Ok(())
+}
@@ -1,16 +1,42 @@
-use anchor_lang::{prelude::*, system_program};
-use crate::PoolState;
+use anchor_lang::prelude::*;
+use crate::emit_event;
+use crate::{PoolState, PoolStatusBitIndex};
 use crate::errors::ClmmError;
-use crate::states::*;
-use crate::states::tick_array;
-use crate::states::tick_array::TickArrayState;
+use crate::libraries::tick_math;
+use crate::states::tick_array::{TickArrayState, TICK_ARRAY_SEED};
+use crate::states::oracle::ObservationState;
+use crate::states::personal_position::{PersonalPositionState, PositionKind, POSITION_SEED};
 use crate::instructions::add_liquidity::add_liquidity;
 use crate::instructions::add_liquidity::LiquidityChangeResult;
+use crate::events::LiquidityChangeEvent;
+use crate::util::token::pool_signer_seeds;
 use anchor_spl::token_interface;
-use anchor_spl::token::{Mint, Token, TokenAccount};
-use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, Token2022};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::{self, CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata};
+use anchor_spl::metadata::mpl_token_metadata::types::{Creator, DataV2};
 
+pub const POSITION_NFT_NAME: &str = "CLMM Position";
+pub const POSITION_NFT_SYMBOL: &str = "CLMM-POS";
+/// Base uri of the off-chain metadata served for position NFTs
+pub const POSITION_NFT_URI: &str = "https://clmm-basic.app/position";
 
+/// Name of a position NFT, e.g. "CLMM Position -120:360", within the 32 byte Metaplex limit
+pub fn position_nft_name(tick_lower_index: i32, tick_upper_index: i32) -> String {
+    format!("{} {}:{}", POSITION_NFT_NAME, tick_lower_index, tick_upper_index)
+}
+
+/// Uri identifying the pool and range of a position NFT
+pub fn position_nft_uri(pool: Pubkey, tick_lower_index: i32, tick_upper_index: i32) -> String {
+    format!(
+        "{}?pool={}&tick_lower={}&tick_upper={}",
+        POSITION_NFT_URI, pool, tick_lower_index, tick_upper_index
+    )
+}
+
+
+    #[cfg_attr(feature = "event-cpi", event_cpi)]
     #[derive(Accounts)]
     #[instruction
     (
@@ -23,6 +49,38 @@ use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, T
         #[account(mut)]
         pub payer: Signer<'info>,
 
+        /// CHECK: Receives the position NFT, can be any address
+        pub position_nft_owner: UncheckedAccount<'info>,
+
+        /// Mint of the position NFT, supply is fixed to 1 once minted
+        #[account(
+            init,
+            mint::decimals = 0,
+            mint::authority = pool_state.key(),
+            mint::freeze_authority = pool_state.key(),
+            payer = payer,
+            mint::token_program = token_program,
+        )]
+        pub position_nft_mint: Box<Account<'info, Mint>>,
+
+        /// Token account holding the position NFT
+        #[account(
+            init,
+            associated_token::mint = position_nft_mint,
+            associated_token::authority = position_nft_owner,
+            payer = payer,
+            associated_token::token_program = token_program,
+        )]
+        pub position_nft_account: Box<Account<'info, TokenAccount>>,
+
+        /// CHECK: Metaplex metadata PDA of the position NFT, checked by the metadata program
+        #[account(mut)]
+        pub metadata_account: UncheckedAccount<'info>,
+
+        /// CHECK: Metaplex master edition PDA of the position NFT, checked by the metadata program
+        #[account(mut)]
+        pub master_edition: UncheckedAccount<'info>,
+
         #[account(mut)]
         pub pool_state: AccountLoader<'info, PoolState>,
 
@@ -30,7 +88,7 @@ use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, T
         #[account(
             mut,
             seeds = [
-                b"tick_array",
+                TICK_ARRAY_SEED.as_bytes(),
                 pool_state.key().as_ref(),
                 &tick_array_lower_start_index.to_be_bytes(),
             ],
@@ -42,7 +100,7 @@ use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, T
         #[account(
             mut,
             seeds = [
-                b"tick_array",
+                TICK_ARRAY_SEED.as_bytes(),
                 pool_state.key().as_ref(),
                 &tick_array_upper_start_index.to_be_bytes(),
             ],
@@ -50,6 +108,23 @@ use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, T
         )]
         pub tick_array_upper: UncheckedAccount<'info>,
 
+        /// Oracle of the pool, written when in-range liquidity changes
+        #[account(mut)]
+        pub observation_state: AccountLoader<'info, ObservationState>,
+
+        /// Liquidity of the position, owned by whoever holds the position NFT
+        #[account(
+            init,
+            seeds = [
+                POSITION_SEED.as_bytes(),
+                position_nft_mint.key().as_ref(),
+            ],
+            bump,
+            payer = payer,
+            space = PersonalPositionState::LEN
+        )]
+        pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
         #[account(
             mut,
             token::mint = token_vault_0.mint
@@ -64,13 +139,13 @@ use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, T
 
         #[account(
             mut,
-            // constraint = token_vault_0.key() == pool_state.token_vault_0
+            constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
         )]
         pub token_vault_0: Box<Account<'info, TokenAccount>>,
 
         #[account(
             mut,
-            // constraint = token_vault_1.key() == pool_state.token_vault_1
+            constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
         )]
         pub token_vault_1: Box<Account<'info, TokenAccount>>,
 
@@ -79,6 +154,10 @@ use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, T
         pub system_program: Program<'info, System>,
 
         pub token_program: Program<'info, Token>,
+
+        pub associated_token_program: Program<'info, AssociatedToken>,
+
+        pub metadata_program: Program<'info, Metadata>,
     }
 
 pub fn open_position_v1<'a, 'b, 'c: 'info, 'info>(
@@ -91,11 +170,17 @@ pub fn open_position_v1<'a, 'b, 'c: 'info, 'info>(
     tick_array_lower_start_index: i32,
     tick_array_upper_start_index: i32,
 ) -> Result<()> {
-    open_position(
+    let liquidity_change_event = open_position(
         &ctx.accounts.payer,
         &ctx.accounts.pool_state,
         &ctx.accounts.tick_array_lower,
-        &ctx.accounts.tick_array_upper,     
+        &ctx.accounts.tick_array_upper,
+        &ctx.accounts.observation_state,
+        ctx.remaining_accounts.first(),
+        &mut ctx.accounts.personal_position,
+        ctx.bumps.personal_position,
+        PositionKind::SplNft,
+        ctx.accounts.position_nft_mint.key(),
         &ctx.accounts.token_account_0.to_account_info(),
         &ctx.accounts.token_account_1.to_account_info(),
         &ctx.accounts.token_vault_0.to_account_info(),
@@ -112,7 +197,26 @@ pub fn open_position_v1<'a, 'b, 'c: 'info, 'info>(
         amount_1_max,
         tick_lower_index,
         tick_upper_index,
-    )
+    )?;
+
+    create_position_nft_with_metadata(
+        &ctx.accounts.payer,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.position_nft_mint,
+        &ctx.accounts.position_nft_account,
+        &ctx.accounts.metadata_account,
+        &ctx.accounts.master_edition,
+        &ctx.accounts.metadata_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+        &ctx.accounts.rent,
+        tick_lower_index,
+        tick_upper_index,
+        ctx.accounts.personal_position.liquidity,
+    )?;
+
+    emit_event!(ctx, liquidity_change_event);
+    Ok(())
 }
 
 pub fn open_position<'a, 'b, 'c: 'info, 'info>(
@@ -120,6 +224,12 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
     pool_state_loader: &'b AccountLoader<'info, PoolState>,
     tick_array_lower_loader: &'b UncheckedAccount<'info>,
     tick_array_upper_loader: &'b UncheckedAccount<'info>,
+    observation_state_loader: &'b AccountLoader<'info, ObservationState>,
+    tick_array_bitmap_extension: Option<&'c AccountInfo<'info>>,
+    personal_position: &mut Account<'info, PersonalPositionState>,
+    personal_position_bump: u8,
+    position_kind: PositionKind,
+    position_nft_mint: Pubkey,
     token_account_0: &'b AccountInfo<'info>,
     token_account_1: &'b AccountInfo<'info>,
     token_vault_0: &'b AccountInfo<'info>,
@@ -136,45 +246,63 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
     amount_1_max: u64,
     tick_lower_index: i32,
     tick_upper_index: i32,
-) -> Result<()> {
+) -> Result<LiquidityChangeEvent> {
 
     let mut liquidity = liquidity;
     {
         let pool_state = &mut pool_state_loader.load_mut()?;
+        require!(
+            pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPosition),
+            ClmmError::OpenPositionDisabled
+        );
+        require!(
+            tick_lower_index < tick_upper_index
+                && tick_lower_index >= tick_math::MIN_TICK
+                && tick_upper_index <= tick_math::MAX_TICK,
+            ClmmError::InvalidTickIndex
+        );
+        require!(
+            tick_lower_index % i32::from(pool_state.tick_spacing) == 0
+                && tick_upper_index % i32::from(pool_state.tick_spacing) == 0,
+            ClmmError::InvalidTickIndex
+        );
 
-        // require_keys_eq!(
-        //     token_vault_0.key(),
-        //     pool_state.token_vault_0,
-        //     ClmmError::InvalidVault
-        // );
-
-        // require_keys_eq!(
-        //     token_vault_1.key(),
-        //     pool_state.token_vault_1,
-        //     ClmmError::InvalidVault
-        // );
+        require_keys_eq!(
+            token_vault_0.key(),
+            pool_state.token_vault_0,
+            ClmmError::InvalidVault
+        );
+
+        require_keys_eq!(
+            token_vault_1.key(),
+            pool_state.token_vault_1,
+            ClmmError::InvalidVault
+        );
         
         let tick_array_lower_loader = TickArrayState::get_or_create_tick_array(
             payer.to_account_info(),
             tick_array_lower_loader.to_account_info(),
             system_program.to_account_info(),
-            &pool_state_loader,
+            pool_state_loader,
             tick_array_lower_start_index,
-            pool_state.tick_spacing,
         )?;
         
         let tick_array_upper_loader = TickArrayState::get_or_create_tick_array(
             payer.to_account_info(),
             tick_array_upper_loader.to_account_info(),
             system_program.to_account_info(),
-            &pool_state_loader,
+            pool_state_loader,
             tick_array_upper_start_index,
-            pool_state.tick_spacing,
         )?;
         
         let LiquidityChangeResult {
             amount_0,
             amount_1,
+            fee_growth_inside_0_x64,
+            fee_growth_inside_1_x64,
+            reward_growths_inside_x64,
+            liquidity_before,
+            liquidity_after,
             ..
         } = add_liquidity(
             payer,
@@ -184,6 +312,8 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
             token_vault_1,
             &tick_array_lower_loader,
             &tick_array_upper_loader,
+            observation_state_loader,
+            tick_array_bitmap_extension,
             token_program_2022,
             token_program,
             vault_0_mint,
@@ -195,7 +325,126 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
             tick_lower_index,
             tick_upper_index,
         )?;
+
+        personal_position.initialize(
+            personal_position_bump,
+            position_kind,
+            position_nft_mint,
+            pool_state_loader.key(),
+            tick_lower_index,
+            tick_upper_index,
+        )?;
+        personal_position.update_fees(fee_growth_inside_0_x64, fee_growth_inside_1_x64)?;
+        personal_position.update_rewards(reward_growths_inside_x64)?;
+        personal_position.update_liquidity(i128::try_from(liquidity).unwrap())?;
+
+        Ok(LiquidityChangeEvent {
+            pool_state: pool_state_loader.key(),
+            position: personal_position.key(),
+            sender: payer.key(),
+            tick_lower: tick_lower_index,
+            tick_upper: tick_upper_index,
+            liquidity_delta: i128::try_from(liquidity).unwrap(),
+            amount_0,
+            amount_1,
+            sqrt_price_x64: pool_state.sqrt_price_x64,
+            tick: pool_state.current_tick,
+            liquidity_before,
+            liquidity_after,
+        })
     }
+}
+
+/// Mints the position NFT to its owner and attaches Metaplex metadata describing the
+/// position plus a master edition which locks supply at 1. The liquidity in the uri
+/// is the one at open, the position account holds the current value
+pub fn create_position_nft_with_metadata<'info>(
+    payer: &Signer<'info>,
+    pool_state_loader: &AccountLoader<'info, PoolState>,
+    position_nft_mint: &Account<'info, Mint>,
+    position_nft_account: &Account<'info, TokenAccount>,
+    metadata_account: &UncheckedAccount<'info>,
+    master_edition: &UncheckedAccount<'info>,
+    metadata_program: &Program<'info, Metadata>,
+    token_program: &Program<'info, Token>,
+    system_program: &Program<'info, System>,
+    rent: &Sysvar<'info, Rent>,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    liquidity: u128,
+) -> Result<()> {
+    let pool = pool_state_loader.load()?;
+    let signer_seeds: &[&[u8]] = &pool_signer_seeds(&pool);
+
+    token::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::MintTo {
+                mint: position_nft_mint.to_account_info(),
+                to: position_nft_account.to_account_info(),
+                authority: pool_state_loader.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        1,
+    )?;
+
+    metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: metadata_account.to_account_info(),
+                mint: position_nft_mint.to_account_info(),
+                mint_authority: pool_state_loader.to_account_info(),
+                payer: payer.to_account_info(),
+                update_authority: pool_state_loader.to_account_info(),
+                system_program: system_program.to_account_info(),
+                rent: rent.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        DataV2 {
+            name: position_nft_name(tick_lower_index, tick_upper_index),
+            symbol: POSITION_NFT_SYMBOL.to_string(),
+            uri: format!(
+                "{}&liquidity={}",
+                position_nft_uri(pool_state_loader.key(), tick_lower_index, tick_upper_index),
+                liquidity
+            ),
+            seller_fee_basis_points: 0,
+            creators: Some(vec![Creator {
+                address: pool_state_loader.key(),
+                verified: true,
+                share: 100,
+            }]),
+            collection: None,
+            uses: None,
+        },
+        true,
+        true,
+        None,
+    )?;
+
+    // Max supply 0 hands the mint and freeze authorities to the edition, so supply stays
+    // at 1 and the NFT can later be burnt through the metadata program
+    metadata::create_master_edition_v3(
+        CpiContext::new_with_signer(
+            metadata_program.to_account_info(),
+            CreateMasterEditionV3 {
+                edition: master_edition.to_account_info(),
+                mint: position_nft_mint.to_account_info(),
+                update_authority: pool_state_loader.to_account_info(),
+                mint_authority: pool_state_loader.to_account_info(),
+                payer: payer.to_account_info(),
+                metadata: metadata_account.to_account_info(),
+                token_program: token_program.to_account_info(),
+                system_program: system_program.to_account_info(),
+                rent: rent.to_account_info(),
+            },
+            &[signer_seeds],
+        ),
+        Some(0),
+    )?;
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+use crate::emit_event;
+use crate::PoolState;
+use crate::states::oracle::ObservationState;
+use crate::states::tick_array::TICK_ARRAY_SEED;
+use crate::util::token::pool_signer_seeds;
+use crate::states::personal_position::{PersonalPositionState, PositionKind, POSITION_SEED};
+use crate::errors::ClmmError;
+use crate::instructions::open_position::{open_position, position_nft_name, position_nft_uri};
+use anchor_lang::error::ErrorCode;
+use anchor_spl::token::{Token, TokenAccount};
+use mpl_core::instructions::{CreateV2CpiBuilder, UpdatePluginV1CpiBuilder};
+use mpl_core::types::{Attribute, Attributes, DataState, Plugin, PluginAuthority, PluginAuthorityPair};
+
+    #[cfg_attr(feature = "event-cpi", event_cpi)]
+    #[derive(Accounts)]
+    #[instruction
+    (
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index:i32,
+        tick_array_upper_start_index:i32,
+    )]
+    pub struct OpenPositionWithCoreAsset<'info> {
+        #[account(mut)]
+        pub payer: Signer<'info>,
+
+        /// CHECK: Receives the position asset, can be any address
+        pub position_asset_owner: UncheckedAccount<'info>,
+
+        /// New keypair for the mpl-core asset representing the position
+        #[account(mut)]
+        pub position_asset: Signer<'info>,
+
+        #[account(mut)]
+        pub pool_state: AccountLoader<'info, PoolState>,
+
+        /// CHECK
+        #[account(
+            mut,
+            seeds = [
+                TICK_ARRAY_SEED.as_bytes(),
+                pool_state.key().as_ref(),
+                &tick_array_lower_start_index.to_be_bytes(),
+            ],
+            bump,
+        )]
+        pub tick_array_lower: UncheckedAccount<'info>,
+
+        /// CHECK
+        #[account(
+            mut,
+            seeds = [
+                TICK_ARRAY_SEED.as_bytes(),
+                pool_state.key().as_ref(),
+                &tick_array_upper_start_index.to_be_bytes(),
+            ],
+            bump,
+        )]
+        pub tick_array_upper: UncheckedAccount<'info>,
+
+        /// Oracle of the pool, written when in-range liquidity changes
+        #[account(mut)]
+        pub observation_state: AccountLoader<'info, ObservationState>,
+
+        /// Liquidity of the position, owned by whoever owns the position asset
+        #[account(
+            init,
+            seeds = [
+                POSITION_SEED.as_bytes(),
+                position_asset.key().as_ref(),
+            ],
+            bump,
+            payer = payer,
+            space = PersonalPositionState::LEN
+        )]
+        pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+        #[account(
+            mut,
+            token::mint = token_vault_0.mint
+        )]
+        pub token_account_0: Box<Account<'info, TokenAccount>>,
+
+        #[account(
+            mut,
+            token::mint = token_vault_1.mint
+        )]
+        pub token_account_1: Box<Account<'info, TokenAccount>>,
+
+        #[account(
+            mut,
+            constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
+        )]
+        pub token_vault_0: Box<Account<'info, TokenAccount>>,
+
+        #[account(
+            mut,
+            constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
+        )]
+        pub token_vault_1: Box<Account<'info, TokenAccount>>,
+
+        pub system_program: Program<'info, System>,
+
+        pub token_program: Program<'info, Token>,
+
+        /// CHECK: Metaplex Core program
+        #[account(address = mpl_core::ID)]
+        pub mpl_core_program: UncheckedAccount<'info>,
+    }
+
+pub fn open_position_with_core_asset<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, OpenPositionWithCoreAsset<'info>>,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+) -> Result<()> {
+    let liquidity_change_event = open_position(
+        &ctx.accounts.payer,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &ctx.accounts.observation_state,
+        ctx.remaining_accounts.first(),
+        &mut ctx.accounts.personal_position,
+        ctx.bumps.personal_position,
+        PositionKind::CoreAsset,
+        ctx.accounts.position_asset.key(),
+        &ctx.accounts.token_account_0.to_account_info(),
+        &ctx.accounts.token_account_1.to_account_info(),
+        &ctx.accounts.token_vault_0.to_account_info(),
+        &ctx.accounts.token_vault_1.to_account_info(),
+        &ctx.accounts.system_program,
+        liquidity,
+        tick_array_lower_start_index,
+        tick_array_upper_start_index,
+        None,
+        &ctx.accounts.token_program,
+        None,
+        None,
+        amount_0_max,
+        amount_1_max,
+        tick_lower_index,
+        tick_upper_index,
+    )?;
+
+    let pool = ctx.accounts.pool_state.load()?;
+    let signer_seeds: &[&[u8]] = &pool_signer_seeds(&pool);
+
+    let attribute_list = position_attributes(&ctx.accounts.personal_position);
+
+    let pool_state_info = ctx.accounts.pool_state.to_account_info();
+    CreateV2CpiBuilder::new(&ctx.accounts.mpl_core_program.to_account_info())
+        .asset(&ctx.accounts.position_asset.to_account_info())
+        .authority(Some(&pool_state_info))
+        .payer(&ctx.accounts.payer.to_account_info())
+        .owner(Some(&ctx.accounts.position_asset_owner.to_account_info()))
+        .update_authority(Some(&pool_state_info))
+        .system_program(&ctx.accounts.system_program.to_account_info())
+        .data_state(DataState::AccountState)
+        .name(position_nft_name(tick_lower_index, tick_upper_index))
+        .uri(position_nft_uri(ctx.accounts.pool_state.key(), tick_lower_index, tick_upper_index))
+        .plugins(vec![PluginAuthorityPair {
+            plugin: Plugin::Attributes(Attributes { attribute_list }),
+            authority: Some(PluginAuthority::UpdateAuthority),
+        }])
+        .invoke_signed(&[signer_seeds])?;
+
+    emit_event!(ctx, liquidity_change_event);
+    Ok(())
+}
+
+/// Attributes plugin of a Core asset position, liquidity is rewritten on every change
+fn position_attributes(personal_position: &PersonalPositionState) -> Vec<Attribute> {
+    vec![
+        Attribute { key: "pool".to_string(), value: personal_position.pool_id.to_string() },
+        Attribute { key: "tick_lower".to_string(), value: personal_position.tick_lower_index.to_string() },
+        Attribute { key: "tick_upper".to_string(), value: personal_position.tick_upper_index.to_string() },
+        Attribute { key: "liquidity".to_string(), value: personal_position.liquidity.to_string() },
+    ]
+}
+
+/// Rewrites the liquidity attribute of a Core asset position after its liquidity
+/// changed, signed by the pool which holds the plugin authority. Does nothing for SPL
+/// NFT positions
+pub fn update_position_asset_liquidity<'info>(
+    payer: &AccountInfo<'info>,
+    pool_state_loader: &AccountLoader<'info, PoolState>,
+    personal_position: &PersonalPositionState,
+    position_asset: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    mpl_core_program: Option<&UncheckedAccount<'info>>,
+) -> Result<()> {
+    if personal_position.position_kind != PositionKind::CoreAsset {
+        return Ok(());
+    }
+    require_keys_eq!(position_asset.key(), personal_position.nft_mint, ClmmError::NotApproved);
+    let mpl_core_program = mpl_core_program.ok_or(ErrorCode::AccountNotEnoughKeys)?;
+
+    let pool = pool_state_loader.load()?;
+    let signer_seeds: &[&[u8]] = &pool_signer_seeds(&pool);
+
+    let pool_state_info = pool_state_loader.to_account_info();
+    UpdatePluginV1CpiBuilder::new(&mpl_core_program.to_account_info())
+        .asset(position_asset)
+        .payer(payer)
+        .authority(Some(&pool_state_info))
+        .system_program(&system_program.to_account_info())
+        .plugin(Plugin::Attributes(Attributes {
+            attribute_list: position_attributes(personal_position),
+        }))
+        .invoke_signed(&[signer_seeds])?;
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use anchor_lang::prelude::*;
+use crate::emit_event;
+use crate::PoolState;
+use crate::states::oracle::ObservationState;
+use crate::states::tick_array::TICK_ARRAY_SEED;
+use crate::states::personal_position::{PersonalPositionState, PositionKind, POSITION_SEED};
+use crate::instructions::open_position::{create_position_nft_with_metadata, open_position};
+use anchor_spl::token::{self, Token};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::Metadata;
+
+/// Same as OpenPosition, for pools holding SPL Token or Token-2022 mints
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+)]
+pub struct OpenPositionV2<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Receives the position NFT, can be any address
+    pub position_nft_owner: UncheckedAccount<'info>,
+
+    /// Mint of the position NFT, supply is fixed to 1 once minted
+    #[account(
+        init,
+        mint::decimals = 0,
+        mint::authority = pool_state.key(),
+        mint::freeze_authority = pool_state.key(),
+        payer = payer,
+        mint::token_program = token_program,
+    )]
+    pub position_nft_mint: Box<Account<'info, token::Mint>>,
+
+    /// Token account holding the position NFT
+    #[account(
+        init,
+        associated_token::mint = position_nft_mint,
+        associated_token::authority = position_nft_owner,
+        payer = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub position_nft_account: Box<Account<'info, token::TokenAccount>>,
+
+    /// CHECK: Metaplex metadata PDA of the position NFT, checked by the metadata program
+    #[account(mut)]
+    pub metadata_account: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex master edition PDA of the position NFT, checked by the metadata program
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// CHECK
+    #[account(
+        mut,
+        seeds = [
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_array_lower_start_index.to_be_bytes(),
+        ],
+        bump,
+    )]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// CHECK
+    #[account(
+        mut,
+        seeds = [
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_array_upper_start_index.to_be_bytes(),
+        ],
+        bump,
+    )]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    /// Oracle of the pool, written when in-range liquidity changes
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    /// Liquidity of the position, owned by whoever holds the position NFT
+    #[account(
+        init,
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            position_nft_mint.key().as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = PersonalPositionState::LEN
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_0.mint
+    )]
+    pub token_account_0: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = token_vault_1.mint
+    )]
+    pub token_account_1: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
+    )]
+    pub token_vault_0: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
+    )]
+    pub token_vault_1: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    pub rent: Sysvar<'info, Rent>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub metadata_program: Program<'info, Metadata>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+}
+
+pub fn open_position_v2<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, OpenPositionV2<'info>>,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+) -> Result<()> {
+    let liquidity_change_event = open_position(
+        &ctx.accounts.payer,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.tick_array_lower,
+        &ctx.accounts.tick_array_upper,
+        &ctx.accounts.observation_state,
+        ctx.remaining_accounts.first(),
+        &mut ctx.accounts.personal_position,
+        ctx.bumps.personal_position,
+        PositionKind::SplNft,
+        ctx.accounts.position_nft_mint.key(),
+        &ctx.accounts.token_account_0.to_account_info(),
+        &ctx.accounts.token_account_1.to_account_info(),
+        &ctx.accounts.token_vault_0.to_account_info(),
+        &ctx.accounts.token_vault_1.to_account_info(),
+        &ctx.accounts.system_program,
+        liquidity,
+        tick_array_lower_start_index,
+        tick_array_upper_start_index,
+        Some(&ctx.accounts.token_program_2022),
+        &ctx.accounts.token_program,
+        Some(ctx.accounts.vault_0_mint.clone()),
+        Some(ctx.accounts.vault_1_mint.clone()),
+        amount_0_max,
+        amount_1_max,
+        tick_lower_index,
+        tick_upper_index,
+    )?;
+
+    create_position_nft_with_metadata(
+        &ctx.accounts.payer,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.position_nft_mint,
+        &ctx.accounts.position_nft_account,
+        &ctx.accounts.metadata_account,
+        &ctx.accounts.master_edition,
+        &ctx.accounts.metadata_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.system_program,
+        &ctx.accounts.rent,
+        tick_lower_index,
+        tick_upper_index,
+        ctx.accounts.personal_position.liquidity,
+    )?;
+
+    emit_event!(ctx, liquidity_change_event);
+    Ok(())
+}
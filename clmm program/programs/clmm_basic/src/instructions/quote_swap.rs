@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::instructions::swap::{load_swap_remaining_accounts, swap_internal};
+use crate::libraries::tick_math;
+use crate::states::config::AmmConfig;
+use crate::states::tick_array::TickArrayState;
+use crate::states::tick_array_bitmap_extension::{TickArrayBitmapExtension, POOL_TICK_ARRAY_BITMAP_SEED};
+use crate::util::token::{get_transfer_fee, get_transfer_inverse_fee};
+use anchor_spl::token_interface::Mint;
+use std::cell::{RefCell, RefMut};
+use std::collections::VecDeque;
+
+/// Tick arrays a quote may walk, counting `tick_array`. Each copy takes about 10 KB
+/// of the 32 KiB program heap, which is never freed
+pub const QUOTE_SWAP_MAX_TICK_ARRAYS: usize = 2;
+
+/// Same pool and tick arrays as a swap, all read-only
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// First initialized tick array at or past the current tick. Further initialized
+    /// arrays follow in remaining_accounts, up to QUOTE_SWAP_MAX_TICK_ARRAYS in all
+    #[account(constraint = tick_array.load()?.pool_id == pool_state.key())]
+    pub tick_array: AccountLoader<'info, TickArrayState>,
+
+    #[account(
+        seeds = [POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+    )]
+    pub tick_array_bitmap_extension: AccountLoader<'info, TickArrayBitmapExtension>,
+
+    #[account(address = pool_state.load()?.token_mint_0)]
+    pub token_mint_0: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pool_state.load()?.token_mint_1)]
+    pub token_mint_1: Box<InterfaceAccount<'info, Mint>>,
+}
+
+/// Written to return data by quote_swap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QuoteSwapResult {
+    /// Paid by the user, including any Token-2022 transfer fee
+    pub amount_in: u64,
+    /// Received by the user, net of any Token-2022 transfer fee
+    pub amount_out: u64,
+    pub sqrt_price_x64: u128,
+    pub tick: i32,
+    pub ticks_crossed: u32,
+}
+
+/// Simulates a swap on copies of the pool and tick arrays and returns the outcome
+/// through return data, leaving every account untouched
+pub fn quote_swap<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, QuoteSwap<'info>>,
+    amount: u64,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> Result<()> {
+    let (input_mint, output_mint) = if zero_for_one {
+        (&ctx.accounts.token_mint_0, &ctx.accounts.token_mint_1)
+    } else {
+        (&ctx.accounts.token_mint_1, &ctx.accounts.token_mint_0)
+    };
+    let amount_specified = if is_base_input {
+        amount
+            .checked_sub(get_transfer_fee(Some(input_mint), amount)?)
+            .ok_or(ClmmError::CalculateOverflow)?
+    } else {
+        amount
+            .checked_add(get_transfer_inverse_fee(Some(output_mint), amount)?)
+            .ok_or(ClmmError::CalculateOverflow)?
+    };
+
+    let pool_id = ctx.accounts.pool_state.key();
+    let tick_array_loaders = load_swap_remaining_accounts(ctx.remaining_accounts)?;
+    require_gt!(
+        QUOTE_SWAP_MAX_TICK_ARRAYS,
+        tick_array_loaders.len(),
+        ClmmError::TooManyTickArrays
+    );
+    let tick_array_bitmap_extension = ctx.accounts.tick_array_bitmap_extension.load()?;
+
+    // swap_internal mutates what it is given, so hand it copies held in RefCells
+    let pool_state_copy = RefCell::new(*ctx.accounts.pool_state.load()?);
+    let mut tick_array_copies =
+        vec![RefCell::new(copy_tick_array(&*ctx.accounts.tick_array.load()?))];
+    for tick_array_loader in tick_array_loaders.iter() {
+        tick_array_copies.push(RefCell::new(copy_tick_array(&*tick_array_loader.load()?)));
+    }
+    let mut tick_array_states: VecDeque<_> = tick_array_copies
+        .iter()
+        .map(|tick_array| {
+            RefMut::map(tick_array.borrow_mut(), |tick_array| tick_array.as_mut())
+        })
+        .collect();
+
+    let limit = if sqrt_price_limit_x64 == 0 {
+        if zero_for_one {
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        } else {
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        }
+    } else {
+        sqrt_price_limit_x64
+    };
+
+    let pool_state = &mut pool_state_copy.borrow_mut();
+    let swap_result = swap_internal(
+        &ctx.accounts.amm_config,
+        pool_state,
+        &pool_id,
+        None,
+        &mut tick_array_states,
+        &tick_array_bitmap_extension,
+        amount_specified,
+        limit,
+        zero_for_one,
+        is_base_input,
+        Clock::get()?.unix_timestamp as u32,
+    )?;
+    require!(
+        swap_result.amount_0 != 0 && swap_result.amount_1 != 0,
+        ClmmError::ZeroSupplyLiquidity
+    );
+
+    let (input_amount, output_amount) = if zero_for_one {
+        (swap_result.amount_0, swap_result.amount_1)
+    } else {
+        (swap_result.amount_1, swap_result.amount_0)
+    };
+    let result = QuoteSwapResult {
+        amount_in: if is_base_input && input_amount == amount_specified {
+            amount
+        } else {
+            input_amount
+                .checked_add(get_transfer_inverse_fee(Some(input_mint), input_amount)?)
+                .ok_or(ClmmError::CalculateOverflow)?
+        },
+        amount_out: output_amount
+            .checked_sub(get_transfer_fee(Some(output_mint), output_amount)?)
+            .ok_or(ClmmError::CalculateOverflow)?,
+        sqrt_price_x64: pool_state.sqrt_price_x64,
+        tick: pool_state.current_tick,
+        ticks_crossed: swap_result.ticks_crossed,
+    };
+    set_return_data(&result.try_to_vec()?);
+
+    Ok(())
+}
+
+/// Copies a tick array straight into a heap allocation, going through a value would
+/// put all 10 KB on the 4 KiB SBF stack frame
+fn copy_tick_array(tick_array: &TickArrayState) -> Box<TickArrayState> {
+    let mut copy = bytemuck::zeroed_box::<TickArrayState>();
+    bytemuck::bytes_of_mut(copy.as_mut()).copy_from_slice(bytemuck::bytes_of(tick_array));
+    copy
+}
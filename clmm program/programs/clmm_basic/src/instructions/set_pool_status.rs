@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::{PoolState, POOL_STATUS_MASK};
+use crate::errors::ClmmError;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+
+#[derive(Accounts)]
+pub struct SetPoolStatus<'info> {
+    #[account(address = global_config.admin @ ClmmError::NotAdmin)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+/// Replaces the pool's status, each set bit of PoolStatusBitIndex disables that operation
+pub fn set_pool_status(ctx: Context<SetPoolStatus>, status: u8) -> Result<()> {
+    require!(status & !POOL_STATUS_MASK == 0, ClmmError::InvalidPoolStatus);
+    ctx.accounts.pool_state.load_mut()?.status = status;
+    Ok(())
+}
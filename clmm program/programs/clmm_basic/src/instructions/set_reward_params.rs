@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::instructions::initialize_reward::fund_reward_vault;
+use crate::{PoolState, REWARD_NUM};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct SetRewardParams<'info> {
+    /// Authority of the reward, set when it was initialized
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = reward_token_mint,
+        token::authority = authority,
+    )]
+    pub authority_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(mut)]
+    pub reward_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub reward_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Changes the emission rate and end time of a reward from now on. An ended reward
+/// restarts now. The authority pays in whatever the vault lacks for the new schedule
+pub fn set_reward_params(
+    ctx: Context<SetRewardParams>,
+    reward_index: u8,
+    emissions_per_second_x64: u128,
+    end_time: u64,
+) -> Result<()> {
+    let reward_index = usize::from(reward_index);
+    require_gt!(REWARD_NUM, reward_index, ClmmError::InvalidRewardIndex);
+    let curr_timestamp = Clock::get()?.unix_timestamp as u64;
+
+    let required_amount = {
+        let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+        // Emissions up to now accrue at the old rate
+        pool_state.update_reward_infos(curr_timestamp)?;
+
+        let reward_info = &mut pool_state.reward_infos[reward_index];
+        require!(reward_info.initialized(), ClmmError::InvalidRewardIndex);
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            reward_info.authority,
+            ClmmError::InvalidRewardAuthority
+        );
+        require_keys_eq!(
+            ctx.accounts.reward_token_vault.key(),
+            reward_info.token_vault,
+            ClmmError::InvalidRewardAccounts
+        );
+        require_keys_eq!(
+            ctx.accounts.reward_token_mint.key(),
+            reward_info.token_mint,
+            ClmmError::InvalidRewardAccounts
+        );
+        require!(
+            end_time > curr_timestamp && end_time > reward_info.open_time,
+            ClmmError::InvalidRewardParams
+        );
+
+        reward_info.emissions_per_second_x64 = emissions_per_second_x64;
+        reward_info.end_time = end_time;
+        reward_info.required_vault_amount(curr_timestamp)?
+    };
+
+    fund_reward_vault(
+        &ctx.accounts.authority,
+        &ctx.accounts.authority_token_account.to_account_info(),
+        &ctx.accounts.reward_token_vault.to_account_info(),
+        ctx.accounts.reward_token_mint.clone(),
+        &ctx.accounts.reward_token_program,
+        ctx.accounts.reward_token_vault.amount,
+        required_amount,
+    )
+}
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::states::oracle::{load_observations, ObservationState};
+use crate::states::tick_array::{get_cumulatives_inside, TickArrayState};
+
+#[derive(Accounts)]
+pub struct SnapshotCumulativesInside<'info> {
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        constraint = observation_state.load()?.pool_id == pool_state.key()
+            @ ClmmError::InvalidObservationState
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    #[account(constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+}
+
+/// Written to return data by snapshot_cumulatives_inside
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotCumulativesInsideResult {
+    pub tick_cumulative_inside: i64,
+    pub seconds_per_liquidity_inside_x64: u128,
+    pub seconds_inside: u32,
+}
+
+/// Returns the cumulatives accrued while the pool traded inside [tick_lower, tick_upper]
+/// through return data. Both ticks must be initialized and only the difference between
+/// two snapshots taken while a position covers the range is meaningful
+pub fn snapshot_cumulatives_inside(
+    ctx: Context<SnapshotCumulativesInside>,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+) -> Result<()> {
+    require!(tick_lower_index < tick_upper_index, ClmmError::InvalidTickIndex);
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let tick_spacing = pool_state.tick_spacing;
+
+    let tick_array_lower = ctx.accounts.tick_array_lower.load()?;
+    let tick_lower_state = tick_array_lower.get_tick_state(tick_lower_index, tick_spacing)?;
+    let tick_array_upper = ctx.accounts.tick_array_upper.load()?;
+    let tick_upper_state = tick_array_upper.get_tick_state(tick_upper_index, tick_spacing)?;
+    require!(
+        tick_lower_state.is_initialized() && tick_upper_state.is_initialized(),
+        ClmmError::TickNotInitialized
+    );
+
+    let (observation_state, observations) =
+        load_observations(ctx.accounts.observation_state.as_ref())?;
+    let block_timestamp = Clock::get()?.unix_timestamp as u32;
+    let (tick_cumulative, seconds_per_liquidity_cumulative_x64) = observation_state.observe_single(
+        &observations,
+        block_timestamp,
+        0,
+        pool_state.current_tick,
+        pool_state.liquidity,
+    )?;
+
+    let (tick_cumulative_inside, seconds_per_liquidity_inside_x64, seconds_inside) =
+        get_cumulatives_inside(
+            tick_lower_state,
+            tick_upper_state,
+            pool_state.current_tick,
+            tick_cumulative,
+            seconds_per_liquidity_cumulative_x64,
+            block_timestamp,
+        );
+
+    let result = SnapshotCumulativesInsideResult {
+        tick_cumulative_inside,
+        seconds_per_liquidity_inside_x64,
+        seconds_inside,
+    };
+    set_return_data(&result.try_to_vec()?);
+    Ok(())
+}
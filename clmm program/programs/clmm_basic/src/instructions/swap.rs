@@ -1,18 +1,36 @@
 use crate::errors::ClmmError;
+use crate::emit_event;
+use crate::events::SwapEvent;
 use crate::libraries::liquidity_math::add_delta;
-use crate::libraries::swap_math::compute_swap_step;
+use crate::libraries::swap_math::{compute_swap_step, FEE_RATE_DENOMINATOR_VALUE};
+use crate::libraries::big_num::U256;
+use crate::libraries::liquidity_math::Q64;
 use crate::libraries::tick_math::{self, get_sqrt_price_at_tick, get_tick_at_sqrt_price};
+use crate::states::config::AmmConfig;
+use crate::states::oracle::{load_pool_observations_mut, Observation, ObservationState};
 use crate::states::tick_array::TickArrayState;
-use crate::util::token::{transfer_from_pool_vault_to_user, transfer_from_user_to_pool_vault};
-use crate::PoolState;
+use crate::states::tick_array_bitmap_extension::{TickArrayBitmapExtension, POOL_TICK_ARRAY_BITMAP_SEED};
+use crate::util::account_load::AccountLoad;
+use crate::util::token::{
+    get_transfer_fee, get_transfer_inverse_fee, transfer_from_pool_vault_to_user,
+    transfer_from_user_to_pool_vault,
+};
+use crate::{PoolState, PoolStatusBitIndex};
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::Mint;
 use std::cell::RefMut;
+use std::collections::VecDeque;
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct Swap<'info> {
     pub payer: Signer<'info>,
 
+    /// Fee tier the pool was created under
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
     #[account(mut)]
     pub pool_state: AccountLoader<'info, PoolState>,
 
@@ -28,9 +46,23 @@ pub struct Swap<'info> {
     #[account(mut)]
     pub output_vault: Box<Account<'info, TokenAccount>>,
 
+    /// Oracle observations of the pool, written by the swap
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    /// First initialized tick array at or past the current tick. Further initialized
+    /// arrays follow in remaining_accounts
     #[account(mut, constraint = tick_array.load()?.pool_id == pool_state.key())]
     pub tick_array: AccountLoader<'info, TickArrayState>,
 
+    /// Initialized tick arrays beyond the pool's own bitmap, searched once the swap
+    /// runs past the last initialized array inside it
+    #[account(
+        seeds = [POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+    )]
+    pub tick_array_bitmap_extension: AccountLoader<'info, TickArrayBitmapExtension>,
+
     pub token_program: Program<'info, Token>,
 }
 
@@ -40,7 +72,21 @@ struct SwapState {
     amount_calculated: u64,
     sqrt_price_x64: u128,
     tick: i32,
+    fee_growth_global_x64: u128,
+    fee_amount: u64,
+    protocol_fee: u64,
     liquidity: u128,
+    ticks_crossed: u32,
+}
+
+/// Totals of a swap_internal run, amounts as the vaults see them
+#[derive(Debug)]
+pub struct SwapResult {
+    pub amount_0: u64,
+    pub amount_1: u64,
+    /// Trading fee charged, protocol share included
+    pub fee_amount: u64,
+    pub ticks_crossed: u32,
 }
 
 #[derive(Default)]
@@ -51,17 +97,23 @@ struct StepComputations {
     sqrt_price_next_x64: u128,
     amount_in: u64,
     amount_out: u64,
+    fee_amount: u64,
 }
 
 pub fn swap_internal(
+    amm_config: &AmmConfig,
     pool_state: &mut RefMut<PoolState>,
-    tick_array: &mut RefMut<TickArrayState>,
+    pool_id: &Pubkey,
+    observation: Option<(&mut ObservationState, &mut [Observation])>,
+    tick_array_states: &mut VecDeque<RefMut<TickArrayState>>,
+    tick_array_bitmap_extension: &TickArrayBitmapExtension,
     amount_specified: u64,
     sqrt_price_limit_x64: u128,
     zero_for_one: bool,
     is_base_input: bool,
-) -> Result<(u64, u64)> {
-    require!(amount_specified != 0, ClmmError::InvalidLiquidity);
+    block_timestamp: u32,
+) -> Result<SwapResult> {
+    require!(amount_specified != 0, ClmmError::ZeroAmountSpecified);
 
     require!(
         if zero_for_one {
@@ -74,28 +126,107 @@ pub fn swap_internal(
         ClmmError::SlippageCheck
     );
 
+    // Observations record the tick and liquidity that held up to this swap. Quotes run
+    // without one and leave the copied ticks' cumulatives outside meaningless
+    let (tick_cumulative, seconds_per_liquidity_cumulative_x64) = match observation {
+        Some((observation_state, observations)) => {
+            observation_state.update(
+                observations,
+                block_timestamp,
+                pool_state.current_tick,
+                pool_state.liquidity,
+            );
+            observation_state.observe_single(
+                observations,
+                block_timestamp,
+                0,
+                pool_state.current_tick,
+                pool_state.liquidity,
+            )?
+        }
+        None => (0, 0),
+    };
+    let reward_growths_global_x64 = pool_state.update_reward_infos(u64::from(block_timestamp))?;
+
     let mut state = SwapState {
         amount_specified_remaining: amount_specified,
         amount_calculated: 0,
         sqrt_price_x64: pool_state.sqrt_price_x64,
         tick: pool_state.current_tick,
+        fee_growth_global_x64: if zero_for_one {
+            pool_state.fee_growth_global_0_x64
+        } else {
+            pool_state.fee_growth_global_1_x64
+        },
+        fee_amount: 0,
+        protocol_fee: 0,
         liquidity: pool_state.liquidity,
+        ticks_crossed: 0,
     };
 
-    let block_timestamp = Clock::get()?.unix_timestamp as u32;
+    // Arrays must be the initialized ones in swap order, starting at the current tick
+    let current_tick_array_start_index =
+        TickArrayState::get_array_start_index(state.tick, pool_state.tick_spacing);
+    let first_tick_array_start_index = if pool_state
+        .is_tick_array_initialized(Some(tick_array_bitmap_extension), current_tick_array_start_index)?
+    {
+        current_tick_array_start_index
+    } else {
+        pool_state
+            .next_initialized_tick_array_start_index(
+                tick_array_bitmap_extension,
+                current_tick_array_start_index,
+                zero_for_one,
+            )?
+            .ok_or(ClmmError::ZeroSupplyLiquidity)?
+    };
+    let mut tick_array_current = tick_array_states
+        .pop_front()
+        .ok_or(ClmmError::NotEnoughTickArrayAccount)?;
+    require_keys_eq!(tick_array_current.pool_id, *pool_id, ClmmError::InvalidTickArray);
+    require_eq!(
+        tick_array_current.start_tick_index,
+        first_tick_array_start_index,
+        ClmmError::InvalidTickArray
+    );
 
     while state.amount_specified_remaining != 0 && state.sqrt_price_x64 != sqrt_price_limit_x64 {
-        let mut step = StepComputations::default();
-        step.sqrt_price_start_x64 = state.sqrt_price_x64;
+        let mut step = StepComputations {
+            sqrt_price_start_x64: state.sqrt_price_x64,
+            ..Default::default()
+        };
 
-        let next_initialized = tick_array.next_initialized_tick(
-            state.tick,
-            pool_state.tick_spacing,
-            zero_for_one,
-        )?;
+        // Skip straight over empty regions using the bitmap
+        let next_initialized = loop {
+            if let Some(tick_state) = tick_array_current.next_initialized_tick(
+                state.tick,
+                pool_state.tick_spacing,
+                zero_for_one,
+            )? {
+                break Some(tick_state.tick);
+            }
+            match pool_state.next_initialized_tick_array_start_index(
+                tick_array_bitmap_extension,
+                tick_array_current.start_tick_index,
+                zero_for_one,
+            )? {
+                Some(next_start_index) => {
+                    tick_array_current = tick_array_states
+                        .pop_front()
+                        .ok_or(ClmmError::NotEnoughTickArrayAccount)?;
+                    require_keys_eq!(tick_array_current.pool_id, *pool_id, ClmmError::InvalidTickArray);
+                    require_eq!(
+                        tick_array_current.start_tick_index,
+                        next_start_index,
+                        ClmmError::InvalidTickArray
+                    );
+                }
+                None => break None,
+            }
+        };
 
-        if let Some(tick_state) = next_initialized {
-            step.tick_next = tick_state.tick;
+        if let Some(tick_next) = next_initialized {
+            step.tick_next = tick_next;
             step.initialized = true;
         } else {
             step.tick_next = if zero_for_one {
@@ -106,11 +237,7 @@ pub fn swap_internal(
             step.initialized = false;
         }
 
-        if step.tick_next < tick_math::MIN_TICK {
-            step.tick_next = tick_math::MIN_TICK;
-        } else if step.tick_next > tick_math::MAX_TICK {
-            step.tick_next = tick_math::MAX_TICK;
-        }
+        step.tick_next = step.tick_next.clamp(tick_math::MIN_TICK, tick_math::MAX_TICK);
         step.sqrt_price_next_x64 = get_sqrt_price_at_tick(step.tick_next)?;
 
         let target_price = if (zero_for_one && step.sqrt_price_next_x64 < sqrt_price_limit_x64)
@@ -126,42 +253,98 @@ pub fn swap_internal(
             target_price,
             state.liquidity,
             state.amount_specified_remaining,
+            amm_config.trade_fee_rate,
             is_base_input,
             zero_for_one,
-            block_timestamp,
         )?;
 
         state.sqrt_price_x64 = swap_step.sqrt_price_next_x64;
         step.amount_in = swap_step.amount_in;
         step.amount_out = swap_step.amount_out;
+        step.fee_amount = swap_step.fee_amount;
 
+        let step_amount_in_with_fee = step
+            .amount_in
+            .checked_add(step.fee_amount)
+            .ok_or(ClmmError::CalculateOverflow)?;
         if is_base_input {
             state.amount_specified_remaining = state
                 .amount_specified_remaining
-                .checked_sub(step.amount_in)
-                .unwrap();
+                .checked_sub(step_amount_in_with_fee)
+                .ok_or(ClmmError::CalculateOverflow)?;
             state.amount_calculated = state
                 .amount_calculated
                 .checked_add(step.amount_out)
-                .unwrap();
+                .ok_or(ClmmError::CalculateOverflow)?;
         } else {
             state.amount_specified_remaining = state
                 .amount_specified_remaining
                 .checked_sub(step.amount_out)
-                .unwrap();
+                .ok_or(ClmmError::CalculateOverflow)?;
             state.amount_calculated = state
                 .amount_calculated
-                .checked_add(step.amount_in)
-                .unwrap();
+                .checked_add(step_amount_in_with_fee)
+                .ok_or(ClmmError::CalculateOverflow)?;
         }
 
-        if state.sqrt_price_x64 == step.sqrt_price_next_x64 && step.initialized {
-            let tick_state = tick_array.get_tick_state_mut(step.tick_next, pool_state.tick_spacing)?;
-            let mut liquidity_net = tick_state.liquidity_net;
-            if zero_for_one {
-                liquidity_net = -liquidity_net;
+        state.fee_amount = state
+            .fee_amount
+            .checked_add(step.fee_amount)
+            .ok_or(ClmmError::CalculateOverflow)?;
+
+        // The protocol takes its cut first, the rest goes to liquidity providers
+        if amm_config.protocol_fee_rate > 0 {
+            let delta = U256::from(step.fee_amount)
+                .mul_div_floor(
+                    U256::from(amm_config.protocol_fee_rate),
+                    U256::from(FEE_RATE_DENOMINATOR_VALUE),
+                )
+                .ok_or(ClmmError::CalculateOverflow)?
+                .as_u64();
+            step.fee_amount = step
+                .fee_amount
+                .checked_sub(delta)
+                .ok_or(ClmmError::CalculateOverflow)?;
+            state.protocol_fee = state
+                .protocol_fee
+                .checked_add(delta)
+                .ok_or(ClmmError::CalculateOverflow)?;
+        }
+
+        // Fees are shared by the liquidity active during this step
+        if state.liquidity > 0 {
+            let fee_growth_global_x64_delta = U256::from(step.fee_amount)
+                .mul_div_floor(U256::from(Q64), U256::from(state.liquidity))
+                .ok_or(ClmmError::CalculateOverflow)?
+                .as_u128();
+            state.fee_growth_global_x64 = state
+                .fee_growth_global_x64
+                .wrapping_add(fee_growth_global_x64_delta);
+        }
+
+        if state.sqrt_price_x64 == step.sqrt_price_next_x64 {
+            if step.initialized {
+                let (fee_growth_global_0_x64, fee_growth_global_1_x64) = if zero_for_one {
+                    (state.fee_growth_global_x64, pool_state.fee_growth_global_1_x64)
+                } else {
+                    (pool_state.fee_growth_global_0_x64, state.fee_growth_global_x64)
+                };
+                let tick_state = tick_array_current
+                    .get_tick_state_mut(step.tick_next, pool_state.tick_spacing)?;
+                let mut liquidity_net = tick_state.cross(
+                    fee_growth_global_0_x64,
+                    fee_growth_global_1_x64,
+                    seconds_per_liquidity_cumulative_x64,
+                    tick_cumulative,
+                    block_timestamp,
+                    reward_growths_global_x64,
+                );
+                if zero_for_one {
+                    liquidity_net = -liquidity_net;
+                }
+                state.liquidity = add_delta(state.liquidity, liquidity_net)?;
+                state.ticks_crossed += 1;
             }
-            state.liquidity = add_delta(state.liquidity, liquidity_net)?;
 
             state.tick = if zero_for_one {
                 step.tick_next - 1
@@ -176,12 +359,25 @@ pub fn swap_internal(
     pool_state.sqrt_price_x64 = state.sqrt_price_x64;
     pool_state.current_tick = state.tick;
     pool_state.liquidity = state.liquidity;
+    if zero_for_one {
+        pool_state.fee_growth_global_0_x64 = state.fee_growth_global_x64;
+        pool_state.protocol_fees_token_0 = pool_state
+            .protocol_fees_token_0
+            .checked_add(state.protocol_fee)
+            .ok_or(ClmmError::CalculateOverflow)?;
+    } else {
+        pool_state.fee_growth_global_1_x64 = state.fee_growth_global_x64;
+        pool_state.protocol_fees_token_1 = pool_state
+            .protocol_fees_token_1
+            .checked_add(state.protocol_fee)
+            .ok_or(ClmmError::CalculateOverflow)?;
+    }
 
     let (amount_0, amount_1) = if zero_for_one == is_base_input {
         (
             amount_specified
                 .checked_sub(state.amount_specified_remaining)
-                .unwrap(),
+                .ok_or(ClmmError::CalculateOverflow)?,
             state.amount_calculated,
         )
     } else {
@@ -189,12 +385,27 @@ pub fn swap_internal(
             state.amount_calculated,
             amount_specified
                 .checked_sub(state.amount_specified_remaining)
-                .unwrap(),
+                .ok_or(ClmmError::CalculateOverflow)?,
         )
     };
 
-    Ok((amount_0, amount_1))
+    Ok(SwapResult {
+        amount_0,
+        amount_1,
+        fee_amount: state.fee_amount,
+        ticks_crossed: state.ticks_crossed,
+    })
 }
+/// Loads the tick arrays a swap traverses after the first one, in swap order
+pub fn load_swap_remaining_accounts<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<Vec<AccountLoad<'info, TickArrayState>>> {
+    remaining_accounts
+        .iter()
+        .map(AccountLoad::<TickArrayState>::try_from)
+        .collect()
+}
+
 pub fn swap<'a, 'b, 'c: 'info, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, Swap<'info>>,
     amount: u64,
@@ -202,29 +413,115 @@ pub fn swap<'a, 'b, 'c: 'info, 'info>(
     sqrt_price_limit_x64: u128,
     is_base_input: bool,
 ) -> Result<()> {
+    let (_, swap_event) = exact_internal(
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.amm_config,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.observation_state,
+        &ctx.accounts.tick_array,
+        &ctx.accounts.tick_array_bitmap_extension,
+        ctx.remaining_accounts,
+        &ctx.accounts.input_token_account.to_account_info(),
+        &ctx.accounts.output_token_account.to_account_info(),
+        &ctx.accounts.input_vault.to_account_info(),
+        &ctx.accounts.output_vault.to_account_info(),
+        None,
+        None,
+        &ctx.accounts.token_program.to_account_info(),
+        None,
+        amount,
+        other_amount_threshold,
+        sqrt_price_limit_x64,
+        is_base_input,
+    )?;
+
+    emit_event!(ctx, swap_event);
+    Ok(())
+}
+
+/// Runs the swap against the pool and settles both legs, shared by the swap entrypoints.
+/// Returns the output received for exact input, or the input paid for exact output,
+/// along with the event describing the swap
+pub fn exact_internal<'c: 'info, 'info>(
+    payer: &AccountInfo<'info>,
+    amm_config: &AmmConfig,
+    pool_state_loader: &AccountLoader<'info, PoolState>,
+    observation_state_loader: &AccountLoader<'info, ObservationState>,
+    tick_array_loader: &AccountLoader<'info, TickArrayState>,
+    tick_array_bitmap_extension_loader: &AccountLoader<'info, TickArrayBitmapExtension>,
+    remaining_accounts: &'c [AccountInfo<'info>],
+    input_token_account: &AccountInfo<'info>,
+    output_token_account: &AccountInfo<'info>,
+    input_vault: &AccountInfo<'info>,
+    output_vault: &AccountInfo<'info>,
+    input_vault_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    output_vault_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+    token_program: &AccountInfo<'info>,
+    token_program_2022: Option<AccountInfo<'info>>,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: u128,
+    is_base_input: bool,
+) -> Result<(u64, SwapEvent)> {
+    // The pool only ever sees amounts net of Token-2022 transfer fees: an exact input
+    // loses its fee before reaching the vault, an exact output must cover the fee
+    // taken on its way to the user
+    let amount_specified = if is_base_input {
+        amount
+            .checked_sub(get_transfer_fee(input_vault_mint.as_deref(), amount)?)
+            .ok_or(ClmmError::CalculateOverflow)?
+    } else {
+        amount
+            .checked_add(get_transfer_inverse_fee(output_vault_mint.as_deref(), amount)?)
+            .ok_or(ClmmError::CalculateOverflow)?
+    };
+
     let amount_0;
     let amount_1;
     let zero_for_one;
-
-    let input_balance_before = ctx.accounts.input_vault.amount;
-    let output_balance_before = ctx.accounts.output_vault.amount;
+    let mut swap_event;
 
     {
-        let pool_state = &mut ctx.accounts.pool_state.load_mut()?;
-        zero_for_one = ctx.accounts.input_vault.mint == pool_state.token_mint_0;
+        let pool_state = &mut pool_state_loader.load_mut()?;
+        require!(
+            pool_state.get_status_by_bit(PoolStatusBitIndex::Swap),
+            ClmmError::SwapDisabled
+        );
+        zero_for_one = input_vault.key() == pool_state.token_vault_0;
 
         require!(
             if zero_for_one {
-                ctx.accounts.input_vault.key() == pool_state.token_vault_0
-                    && ctx.accounts.output_vault.key() == pool_state.token_vault_1
+                output_vault.key() == pool_state.token_vault_1
             } else {
-                ctx.accounts.input_vault.key() == pool_state.token_vault_1
-                    && ctx.accounts.output_vault.key() == pool_state.token_vault_0
+                input_vault.key() == pool_state.token_vault_1
+                    && output_vault.key() == pool_state.token_vault_0
             },
             ClmmError::InvalidVault
         );
 
-        let tick_array = &mut ctx.accounts.tick_array.load_mut()?;
+        let tick_array_loaders = load_swap_remaining_accounts(remaining_accounts)?;
+        let tick_array_bitmap_extension = tick_array_bitmap_extension_loader.load()?;
+        let mut tick_array_states = VecDeque::new();
+        tick_array_states.push_back(tick_array_loader.load_mut()?);
+        for tick_array_loader in tick_array_loaders.iter() {
+            tick_array_states.push_back(tick_array_loader.load_mut()?);
+        }
+
+        swap_event = SwapEvent {
+            pool_state: pool_state_loader.key(),
+            sender: payer.key(),
+            input_token_account: input_token_account.key(),
+            output_token_account: output_token_account.key(),
+            zero_for_one,
+            amount_0: 0,
+            amount_1: 0,
+            sqrt_price_x64_before: pool_state.sqrt_price_x64,
+            sqrt_price_x64_after: 0,
+            tick_before: pool_state.current_tick,
+            tick_after: 0,
+            liquidity_before: pool_state.liquidity,
+            liquidity_after: 0,
+        };
 
         let limit = if sqrt_price_limit_x64 == 0 {
             if zero_for_one {
@@ -236,80 +533,84 @@ pub fn swap<'a, 'b, 'c: 'info, 'info>(
             sqrt_price_limit_x64
         };
 
-        (amount_0, amount_1) = swap_internal(
+        let (mut observation_state, mut observations) =
+            load_pool_observations_mut(observation_state_loader.as_ref(), &pool_state_loader.key())?;
+
+        let swap_result = swap_internal(
+            amm_config,
             pool_state,
-            tick_array,
-            amount,
+            &pool_state_loader.key(),
+            Some((&mut observation_state, &mut observations)),
+            &mut tick_array_states,
+            &tick_array_bitmap_extension,
+            amount_specified,
             limit,
             zero_for_one,
             is_base_input,
+            Clock::get()?.unix_timestamp as u32,
         )?;
+        amount_0 = swap_result.amount_0;
+        amount_1 = swap_result.amount_1;
 
         require!(amount_0 != 0 && amount_1 != 0, ClmmError::ZeroSupplyLiquidity);
-    }
 
-    if zero_for_one {
-        transfer_from_user_to_pool_vault(
-            &ctx.accounts.payer.to_account_info(),
-            &ctx.accounts.input_token_account.to_account_info(),
-            &ctx.accounts.input_vault.to_account_info(),
-            None,
-            &ctx.accounts.token_program.to_account_info(),
-            None,
-            amount_0,
-        )?;
+        swap_event.amount_0 = amount_0;
+        swap_event.amount_1 = amount_1;
+        swap_event.sqrt_price_x64_after = pool_state.sqrt_price_x64;
+        swap_event.tick_after = pool_state.current_tick;
+        swap_event.liquidity_after = pool_state.liquidity;
+    }
 
-        transfer_from_pool_vault_to_user(
-            &ctx.accounts.pool_state,
-            &ctx.accounts.output_vault.to_account_info(),
-            &ctx.accounts.output_token_account.to_account_info(),
-            None,
-            &ctx.accounts.token_program.to_account_info(),
-            None,
-            amount_1,
-        )?;
+    let (input_amount, output_amount) = if zero_for_one {
+        (amount_0, amount_1)
     } else {
-        transfer_from_user_to_pool_vault(
-            &ctx.accounts.payer.to_account_info(),
-            &ctx.accounts.input_token_account.to_account_info(),
-            &ctx.accounts.input_vault.to_account_info(),
-            None,
-            &ctx.accounts.token_program.to_account_info(),
-            None,
-            amount_1,
-        )?;
+        (amount_1, amount_0)
+    };
 
-        transfer_from_pool_vault_to_user(
-            &ctx.accounts.pool_state,
-            &ctx.accounts.output_vault.to_account_info(),
-            &ctx.accounts.output_token_account.to_account_info(),
-            None,
-            &ctx.accounts.token_program.to_account_info(),
-            None,
-            amount_0,
-        )?;
-    }
+    // What the user sends so the vault receives input_amount
+    let input_transfer_amount = if is_base_input && input_amount == amount_specified {
+        amount
+    } else {
+        input_amount
+            .checked_add(get_transfer_inverse_fee(input_vault_mint.as_deref(), input_amount)?)
+            .ok_or(ClmmError::CalculateOverflow)?
+    };
 
+    let output_received = output_amount
+        .checked_sub(get_transfer_fee(output_vault_mint.as_deref(), output_amount)?)
+        .ok_or(ClmmError::CalculateOverflow)?;
     if is_base_input {
-        let output_amount = output_balance_before
-            .checked_sub(ctx.accounts.output_vault.amount)
-            .unwrap();
-        require!(
-            output_amount >= other_amount_threshold,
-            ClmmError::SlippageCheck
-        );
+        require_gte!(output_received, other_amount_threshold, ClmmError::SlippageCheck);
     } else {
-        let input_amount = ctx
-            .accounts
-            .input_vault
-            .amount
-            .checked_sub(input_balance_before)
-            .unwrap();
-        require!(
-            input_amount <= other_amount_threshold,
-            ClmmError::SlippageCheck
-        );
+        // A price limit that stops the swap early must not leave the buyer short
+        require_eq!(output_amount, amount_specified, ClmmError::SlippageCheck);
+        require_gte!(other_amount_threshold, input_transfer_amount, ClmmError::SlippageCheck);
     }
 
-    Ok(())
+    transfer_from_user_to_pool_vault(
+        payer,
+        input_token_account,
+        input_vault,
+        input_vault_mint,
+        token_program,
+        token_program_2022.clone(),
+        input_transfer_amount,
+    )?;
+
+    transfer_from_pool_vault_to_user(
+        pool_state_loader,
+        output_vault,
+        output_token_account,
+        output_vault_mint,
+        token_program,
+        token_program_2022,
+        output_amount,
+    )?;
+
+    let amount = if is_base_input {
+        output_received
+    } else {
+        input_transfer_amount
+    };
+    Ok((amount, swap_event))
 }
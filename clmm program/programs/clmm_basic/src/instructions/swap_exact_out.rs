@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::emit_event;
+use crate::instructions::swap::exact_internal;
+use crate::instructions::swap_v2::SwapSingleV2;
+
+/// Buys exactly `amount_out` of the output token, failing before any transfer if
+/// that would cost more than `amount_in_maximum`
+pub fn swap_exact_out<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+    amount_out: u64,
+    amount_in_maximum: u64,
+    sqrt_price_limit_x64: u128,
+) -> Result<()> {
+    let (_, swap_event) = exact_internal(
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.amm_config,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.observation_state,
+        &ctx.accounts.tick_array,
+        &ctx.accounts.tick_array_bitmap_extension,
+        ctx.remaining_accounts,
+        &ctx.accounts.input_token_account.to_account_info(),
+        &ctx.accounts.output_token_account.to_account_info(),
+        &ctx.accounts.input_vault.to_account_info(),
+        &ctx.accounts.output_vault.to_account_info(),
+        Some(ctx.accounts.input_vault_mint.clone()),
+        Some(ctx.accounts.output_vault_mint.clone()),
+        &ctx.accounts.token_program.to_account_info(),
+        Some(ctx.accounts.token_program_2022.to_account_info()),
+        amount_out,
+        amount_in_maximum,
+        sqrt_price_limit_x64,
+        false,
+    )?;
+
+    emit_event!(ctx, swap_event);
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use anchor_lang::prelude::*;
+use anchor_lang::error::ErrorCode;
+use crate::emit_event;
+use crate::PoolState;
+use crate::errors::ClmmError;
+use crate::instructions::swap::exact_internal;
+use crate::states::config::AmmConfig;
+use crate::states::oracle::ObservationState;
+use crate::states::tick_array::TickArrayState;
+use crate::states::tick_array_bitmap_extension::{TickArrayBitmapExtension, POOL_TICK_ARRAY_BITMAP_SEED};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// Accounts at the start of every hop in remaining_accounts: amm_config, pool_state,
+/// observation_state, input_vault, output_vault, output_token_account,
+/// output_vault_mint, the first tick array and the pool's bitmap extension. The hop's
+/// further tick arrays follow, as many as its entry in `tick_array_counts`
+const HOP_ACCOUNTS_LEN: usize = 9;
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SwapRouterBaseIn<'info> {
+    /// Owns every token account along the route
+    pub payer: Signer<'info>,
+
+    /// Token account paying the first hop
+    #[account(mut)]
+    pub input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = input_token_account.mint)]
+    pub input_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+}
+
+pub fn swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseIn<'info>>,
+    amount_in: u64,
+    amount_out_minimum: u64,
+    tick_array_counts: Vec<u8>,
+) -> Result<()> {
+    let mut amount_in_next = amount_in;
+    let mut input_token_account = ctx.accounts.input_token_account.to_account_info();
+    let mut input_token_mint = ctx.accounts.input_token_mint.clone();
+
+    let mut accounts: &'c [AccountInfo<'info>] = ctx.remaining_accounts;
+    require!(!tick_array_counts.is_empty(), ErrorCode::AccountNotEnoughKeys);
+    for tick_array_count in tick_array_counts {
+        let hop_end = HOP_ACCOUNTS_LEN + usize::from(tick_array_count);
+        require_gte!(accounts.len(), hop_end, ErrorCode::AccountNotEnoughKeys);
+
+        let amm_config = Account::<AmmConfig>::try_from(&accounts[0])?;
+        let pool_state_loader = AccountLoader::<PoolState>::try_from(&accounts[1])?;
+        require_keys_eq!(
+            pool_state_loader.load()?.amm_config,
+            amm_config.key(),
+            ClmmError::InvalidAmmConfig
+        );
+        let observation_state_loader = AccountLoader::<ObservationState>::try_from(&accounts[2])?;
+        let input_vault = &accounts[3];
+        let output_vault = &accounts[4];
+        let output_token_account = &accounts[5];
+        let output_vault_mint = Box::new(InterfaceAccount::<Mint>::try_from(&accounts[6])?);
+        let tick_array_loader = AccountLoader::<TickArrayState>::try_from(&accounts[7])?;
+
+        // Each hop must take what the previous one paid out and pay out what it names
+        require_keys_eq!(
+            InterfaceAccount::<TokenAccount>::try_from(input_vault)?.mint,
+            input_token_mint.key(),
+            ClmmError::InvalidTokenMint
+        );
+        require_keys_eq!(
+            InterfaceAccount::<TokenAccount>::try_from(output_vault)?.mint,
+            output_vault_mint.key(),
+            ClmmError::InvalidTokenMint
+        );
+        require_keys_eq!(
+            InterfaceAccount::<TokenAccount>::try_from(output_token_account)?.mint,
+            output_vault_mint.key(),
+            ClmmError::InvalidTokenMint
+        );
+
+        let (tick_array_bitmap_extension_key, _) = Pubkey::find_program_address(
+            &[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_state_loader.key().as_ref()],
+            &crate::id(),
+        );
+        require_keys_eq!(
+            accounts[8].key(),
+            tick_array_bitmap_extension_key,
+            ClmmError::InvalidTickArrayBitmapExtension
+        );
+        let tick_array_bitmap_extension_loader =
+            AccountLoader::<TickArrayBitmapExtension>::try_from(&accounts[8])?;
+
+        // Intermediate hops take whatever they are given, slippage is checked once at the end
+        let swap_event;
+        (amount_in_next, swap_event) = exact_internal(
+            &ctx.accounts.payer.to_account_info(),
+            &amm_config,
+            &pool_state_loader,
+            &observation_state_loader,
+            &tick_array_loader,
+            &tick_array_bitmap_extension_loader,
+            &accounts[HOP_ACCOUNTS_LEN..hop_end],
+            &input_token_account,
+            output_token_account,
+            input_vault,
+            output_vault,
+            Some(input_token_mint),
+            Some(output_vault_mint.clone()),
+            &ctx.accounts.token_program.to_account_info(),
+            Some(ctx.accounts.token_program_2022.to_account_info()),
+            amount_in_next,
+            0,
+            0,
+            true,
+        )?;
+        emit_event!(ctx, swap_event);
+
+        input_token_account = output_token_account.clone();
+        input_token_mint = output_vault_mint;
+        accounts = &accounts[hop_end..];
+    }
+
+    require_gte!(amount_in_next, amount_out_minimum, ClmmError::SlippageCheck);
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use crate::emit_event;
+use crate::PoolState;
+use crate::instructions::swap::exact_internal;
+use crate::states::config::AmmConfig;
+use crate::states::oracle::ObservationState;
+use crate::states::tick_array::TickArrayState;
+use crate::states::tick_array_bitmap_extension::{TickArrayBitmapExtension, POOL_TICK_ARRAY_BITMAP_SEED};
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// Same as Swap, for pools holding SPL Token or Token-2022 mints
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct SwapSingleV2<'info> {
+    pub payer: Signer<'info>,
+
+    /// Fee tier the pool was created under
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(mut)]
+    pub input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub input_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub output_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Oracle observations of the pool, written by the swap
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    /// First initialized tick array at or past the current tick. Further initialized
+    /// arrays follow in remaining_accounts
+    #[account(mut, constraint = tick_array.load()?.pool_id == pool_state.key())]
+    pub tick_array: AccountLoader<'info, TickArrayState>,
+
+    /// Initialized tick arrays beyond the pool's own bitmap, searched once the swap
+    /// runs past the last initialized array inside it
+    #[account(
+        seeds = [POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+    )]
+    pub tick_array_bitmap_extension: AccountLoader<'info, TickArrayBitmapExtension>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    #[account(address = input_vault.mint)]
+    pub input_vault_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = output_vault.mint)]
+    pub output_vault_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+pub fn swap_v2<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: u128,
+    is_base_input: bool,
+) -> Result<()> {
+    let (_, swap_event) = exact_internal(
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.amm_config,
+        &ctx.accounts.pool_state,
+        &ctx.accounts.observation_state,
+        &ctx.accounts.tick_array,
+        &ctx.accounts.tick_array_bitmap_extension,
+        ctx.remaining_accounts,
+        &ctx.accounts.input_token_account.to_account_info(),
+        &ctx.accounts.output_token_account.to_account_info(),
+        &ctx.accounts.input_vault.to_account_info(),
+        &ctx.accounts.output_vault.to_account_info(),
+        Some(ctx.accounts.input_vault_mint.clone()),
+        Some(ctx.accounts.output_vault_mint.clone()),
+        &ctx.accounts.token_program.to_account_info(),
+        Some(ctx.accounts.token_program_2022.to_account_info()),
+        amount,
+        other_amount_threshold,
+        sqrt_price_limit_x64,
+        is_base_input,
+    )?;
+
+    emit_event!(ctx, swap_event);
+    Ok(())
+}
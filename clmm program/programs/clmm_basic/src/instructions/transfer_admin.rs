@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::states::global_config::{GlobalConfig, GLOBAL_CONFIG_SEED};
+
+#[derive(Accounts)]
+pub struct TransferAdmin<'info> {
+    #[account(address = global_config.admin @ ClmmError::NotAdmin)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_CONFIG_SEED.as_bytes()],
+        bump = global_config.bump,
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+/// Proposes `new_admin`, who takes over only after calling accept_admin. The
+/// default pubkey cancels a pending transfer
+pub fn transfer_admin(ctx: Context<TransferAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.global_config.pending_admin = new_admin;
+    Ok(())
+}
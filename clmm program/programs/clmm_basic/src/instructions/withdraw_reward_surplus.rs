@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::util::token::transfer_from_pool_vault_to_user;
+use crate::{PoolState, REWARD_NUM};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct WithdrawRewardSurplus<'info> {
+    /// Authority of the reward, set when it was initialized
+    pub authority: Signer<'info>,
+
+    #[account(mut, token::mint = reward_token_mint)]
+    pub recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(mut)]
+    pub reward_token_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub reward_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+}
+
+/// Returns what an ended reward never emitted to its authority. Seconds without
+/// in-range liquidity emit nothing, so the schedule funded up front can leave more in
+/// the vault than positions will ever claim. Everything emitted but unclaimed stays
+pub fn withdraw_reward_surplus(ctx: Context<WithdrawRewardSurplus>, reward_index: u8) -> Result<()> {
+    let reward_index = usize::from(reward_index);
+    require_gt!(REWARD_NUM, reward_index, ClmmError::InvalidRewardIndex);
+    let curr_timestamp = Clock::get()?.unix_timestamp as u64;
+
+    let required_amount = {
+        let mut pool_state = ctx.accounts.pool_state.load_mut()?;
+        pool_state.update_reward_infos(curr_timestamp)?;
+
+        let reward_info = &pool_state.reward_infos[reward_index];
+        require!(reward_info.initialized(), ClmmError::InvalidRewardIndex);
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            reward_info.authority,
+            ClmmError::InvalidRewardAuthority
+        );
+        require_keys_eq!(
+            ctx.accounts.reward_token_vault.key(),
+            reward_info.token_vault,
+            ClmmError::InvalidRewardAccounts
+        );
+        require_keys_eq!(
+            ctx.accounts.reward_token_mint.key(),
+            reward_info.token_mint,
+            ClmmError::InvalidRewardAccounts
+        );
+        require_gte!(curr_timestamp, { reward_info.end_time }, ClmmError::RewardNotEnded);
+        reward_info.required_vault_amount(curr_timestamp)?
+    };
+
+    transfer_from_pool_vault_to_user(
+        &ctx.accounts.pool_state,
+        &ctx.accounts.reward_token_vault.to_account_info(),
+        &ctx.accounts.recipient_token_account.to_account_info(),
+        Some(ctx.accounts.reward_token_mint.clone()),
+        &ctx.accounts.reward_token_program.to_account_info(),
+        Some(ctx.accounts.reward_token_program.to_account_info()),
+        ctx.accounts.reward_token_vault.amount.saturating_sub(required_amount),
+    )
+}
@@ -1,31 +1,101 @@
+#![allow(clippy::too_many_arguments)]
+// Anchor 0.31 emits the IDL instructions, which call the deprecated AccountInfo::realloc,
+// in a private module beside #[program] that takes no attributes. Only that module keeps
+// the allow, everything written here opts back in with warn(deprecated)
+#![allow(deprecated)]
+#[warn(deprecated)]
 pub mod util;
+#[warn(deprecated)]
 pub mod errors;
+#[warn(deprecated)]
+pub mod events;
+#[warn(deprecated)]
 pub mod libraries;
+#[warn(deprecated)]
 pub mod states;
+#[warn(deprecated)]
 pub mod instructions;
 
 use anchor_lang::prelude::*;
 use crate::util::*;
 use crate::libraries::*;
-use crate::states::*;
+use crate::libraries::big_num::U256;
+use crate::libraries::liquidity_math::Q64;
+use crate::states::config::AmmConfig;
+use crate::states::oracle::{
+    load_observations_mut, ObservationState, OBSERVATION_CARDINALITY_INITIAL, OBSERVATION_SEED,
+};
+use crate::states::token_badge::{TokenBadge, TOKEN_BADGE_SEED};
+use crate::states::tick_array_bitmap_extension::{
+    self, TickArrayBitmapExtension, POOL_TICK_ARRAY_BITMAP_SEED, POOL_TICK_ARRAY_BITMAP_WORDS,
+};
 use crate::instructions::*;
-use anchor_spl::token_interface;
-use crate::errors::*;
-
-use anchor_spl::token::{self, InitializeAccount, Token, TokenAccount, Transfer};
+use crate::errors::ClmmError;
+use crate::events::PoolCreatedEvent;
 
 use anchor_spl::token_interface::{Mint, TokenInterface};
 
 declare_id!("ERVBWDNeqHjarW6En4X9383tJxCGS1i2htLNUyFoBeej");
 
+/// Bootstrap key, the only signer create_global_config accepts. Every other admin
+/// check reads GlobalConfig.admin, so transferring the admin leaves this key behind
+pub mod admin {
+    use anchor_lang::prelude::declare_id;
+    declare_id!("GyKuwQiHKEf69VDrUgmGhr5qXvfwZFoPA5pmzYFgiJmt");
+}
+
 #[program]
+#[warn(deprecated)]
 pub mod clmm_basic {
     use super::*;
 
+    pub fn create_amm_config(
+        ctx: Context<CreateAmmConfig>,
+        index: u16,
+        tick_spacing: u16,
+        trade_fee_rate: u32,
+        protocol_fee_rate: u32,
+    ) -> Result<()> {
+        instructions::create_amm_config::create_amm_config(
+            ctx,
+            index,
+            tick_spacing,
+            trade_fee_rate,
+            protocol_fee_rate,
+        )
+    }
+
+    pub fn create_token_badge(ctx: Context<CreateTokenBadge>) -> Result<()> {
+        instructions::create_token_badge::create_token_badge(ctx)
+    }
+
+    pub fn create_global_config(ctx: Context<CreateGlobalConfig>) -> Result<()> {
+        instructions::create_global_config::create_global_config(ctx)
+    }
+
+    pub fn transfer_admin(ctx: Context<TransferAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::transfer_admin::transfer_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin::accept_admin(ctx)
+    }
+
+    pub fn set_pool_status(ctx: Context<SetPoolStatus>, status: u8) -> Result<()> {
+        instructions::set_pool_status::set_pool_status(ctx, status)
+    }
+
     pub fn create_pool(ctx: Context<CreatePool>, sqrt_price_x64: u128) -> Result<()> {
+    // Badged mints skip the extension checks
+    if ctx.accounts.token_badge_0.is_none() {
+        check_mint_extensions(&ctx.accounts.token_mint_0)?;
+    }
+    if ctx.accounts.token_badge_1.is_none() {
+        check_mint_extensions(&ctx.accounts.token_mint_1)?;
+    }
 
     // let pool_state = &mut ctx.accounts.pool_state;
-    let mut pool_state = &mut ctx.accounts.pool_state.load_init()?;
+    let pool_state = &mut ctx.accounts.pool_state.load_init()?;
 
     let tick = get_tick_at_sqrt_price(sqrt_price_x64)?;
     
@@ -60,19 +130,47 @@ pub mod clmm_basic {
             ],
         )?;
 
+        ctx.accounts
+            .tick_array_bitmap
+            .load_init()?
+            .initialize(ctx.accounts.pool_state.key());
+
+        {
+            let (mut observation_state, mut observations) =
+                load_observations_mut(ctx.accounts.observation_state.as_ref())?;
+            observation_state.initialize(
+                &mut observations,
+                ctx.accounts.pool_state.key(),
+                Clock::get()?.unix_timestamp as u32,
+            );
+        }
+
         let bump = ctx.bumps.pool_state;
-        let tick_spacing: u128 = 1;
 
         pool_state.initialize(
+            ctx.accounts.amm_config.key(),
             ctx.accounts.token_mint_0.as_ref(),
             ctx.accounts.token_mint_1.as_ref(),
             ctx.accounts.token_vault_0.key(),
             ctx.accounts.token_vault_1.key(),
-            tick_spacing as u16,
+            ctx.accounts.amm_config.tick_spacing,
             sqrt_price_x64,
             tick,
             bump,
         )?;
+
+        emit_event!(ctx, PoolCreatedEvent {
+            pool_state: ctx.accounts.pool_state.key(),
+            pool_creator: ctx.accounts.pool_creator.key(),
+            amm_config: ctx.accounts.amm_config.key(),
+            token_mint_0: ctx.accounts.token_mint_0.key(),
+            token_mint_1: ctx.accounts.token_mint_1.key(),
+            token_vault_0: ctx.accounts.token_vault_0.key(),
+            token_vault_1: ctx.accounts.token_vault_1.key(),
+            tick_spacing: ctx.accounts.amm_config.tick_spacing,
+            sqrt_price_x64,
+            tick,
+        });
         Ok(())
     }
 
@@ -98,6 +196,50 @@ pub mod clmm_basic {
         )
     }
 
+    pub fn open_position_with_core_asset<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, OpenPositionWithCoreAsset<'info>>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    ) -> Result<()> {
+        instructions::open_position_core::open_position_with_core_asset(
+            ctx,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+        )
+    }
+
+    pub fn open_position_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, OpenPositionV2<'info>>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    ) -> Result<()> {
+        instructions::open_position_v2::open_position_v2(
+            ctx,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+        )
+    }
+
     pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidity<'info>>,
         liquidity: u128,
@@ -116,6 +258,24 @@ pub mod clmm_basic {
         )
     }
 
+    pub fn increase_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidityV2<'info>>,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<()> {
+        instructions::increase_liquidity_v2::increase_liquidity_v2(
+            ctx,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            tick_lower_index,
+            tick_upper_index,
+        )
+    }
+
     pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidity<'info>>,
         liquidity: u128,
@@ -124,24 +284,62 @@ pub mod clmm_basic {
         tick_lower_index: i32,
         tick_upper_index: i32,
     ) -> Result<()> {
-    crate::instructions::decrease_liquidity::decrease_liquidity(
-        &ctx.accounts.pool_state,
-        &ctx.accounts.token_vault_0.to_account_info(),
-        &ctx.accounts.token_vault_1.to_account_info(),
-        &ctx.accounts.tick_array_lower,
-        &ctx.accounts.tick_array_upper,
-        &ctx.accounts.recipient_token_account_0.to_account_info(),
-        &ctx.accounts.recipient_token_account_1.to_account_info(),
-        &ctx.accounts.token_program,
-        None,
-        None,
-        None,
-        liquidity,
-        amount_0_min,
-        amount_1_min,
-        tick_lower_index,
-        tick_upper_index
-    )
+        decrease_liquidity_v1(
+            ctx,
+            liquidity,
+            amount_0_min,
+            amount_1_min,
+            tick_lower_index,
+            tick_upper_index,
+        )
+    }
+
+    pub fn decrease_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityV2<'info>>,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<()> {
+        instructions::decrease_liquidity_v2::decrease_liquidity_v2(
+            ctx,
+            liquidity,
+            amount_0_min,
+            amount_1_min,
+            tick_lower_index,
+            tick_upper_index,
+        )
+    }
+
+    pub fn close_position<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ClosePosition<'info>>,
+    ) -> Result<()> {
+        instructions::close_position::close_position(ctx)
+    }
+
+    pub fn collect_fees<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CollectFees<'info>>,
+        amount_0_requested: u64,
+        amount_1_requested: u64,
+    ) -> Result<()> {
+        instructions::collect_fees::collect_fees(ctx, amount_0_requested, amount_1_requested)
+    }
+
+    pub fn collect_protocol_fee(
+        ctx: Context<CollectProtocolFee>,
+        amount_0_requested: u64,
+        amount_1_requested: u64,
+    ) -> Result<()> {
+        instructions::collect_protocol_fee::collect_protocol_fee(
+            ctx,
+            amount_0_requested,
+            amount_1_requested,
+        )
+    }
+
+    pub fn close_tick_array(ctx: Context<CloseTickArray>) -> Result<()> {
+        instructions::close_tick_array::close_tick_array(ctx)
     }
 
     pub fn swap<'a, 'b, 'c: 'info, 'info>(
@@ -153,15 +351,128 @@ pub mod clmm_basic {
     ) -> Result<()> {
         instructions::swap::swap(ctx, amount, other_amount_threshold, sqrt_price_limit_x64, is_base_input)
     }
+
+    pub fn swap_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+    ) -> Result<()> {
+        instructions::swap_v2::swap_v2(ctx, amount, other_amount_threshold, sqrt_price_limit_x64, is_base_input)
+    }
+
+    pub fn swap_exact_out<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+        amount_out: u64,
+        amount_in_maximum: u64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<()> {
+        instructions::swap_exact_out::swap_exact_out(ctx, amount_out, amount_in_maximum, sqrt_price_limit_x64)
+    }
+
+    pub fn swap_router_base_in<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapRouterBaseIn<'info>>,
+        amount_in: u64,
+        amount_out_minimum: u64,
+        tick_array_counts: Vec<u8>,
+    ) -> Result<()> {
+        instructions::swap_router_base_in::swap_router_base_in(
+            ctx,
+            amount_in,
+            amount_out_minimum,
+            tick_array_counts,
+        )
+    }
+
+    pub fn quote_swap<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, QuoteSwap<'info>>,
+        amount: u64,
+        sqrt_price_limit_x64: u128,
+        zero_for_one: bool,
+        is_base_input: bool,
+    ) -> Result<()> {
+        instructions::quote_swap::quote_swap(ctx, amount, sqrt_price_limit_x64, zero_for_one, is_base_input)
+    }
+
+    pub fn observe(ctx: Context<Observe>, twap_windows: Vec<u32>) -> Result<()> {
+        instructions::observe::observe(ctx, twap_windows)
+    }
+
+    pub fn increase_observation_cardinality_next(
+        ctx: Context<IncreaseObservationCardinalityNext>,
+        observation_cardinality_next: u16,
+    ) -> Result<()> {
+        instructions::increase_observation_cardinality_next::increase_observation_cardinality_next(
+            ctx,
+            observation_cardinality_next,
+        )
+    }
+
+    pub fn snapshot_cumulatives_inside(
+        ctx: Context<SnapshotCumulativesInside>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<()> {
+        instructions::snapshot_cumulatives_inside::snapshot_cumulatives_inside(
+            ctx,
+            tick_lower_index,
+            tick_upper_index,
+        )
+    }
+
+    pub fn initialize_reward(
+        ctx: Context<InitializeReward>,
+        open_time: u64,
+        end_time: u64,
+        emissions_per_second_x64: u128,
+    ) -> Result<()> {
+        instructions::initialize_reward::initialize_reward(
+            ctx,
+            open_time,
+            end_time,
+            emissions_per_second_x64,
+        )
+    }
+
+    pub fn set_reward_params(
+        ctx: Context<SetRewardParams>,
+        reward_index: u8,
+        emissions_per_second_x64: u128,
+        end_time: u64,
+    ) -> Result<()> {
+        instructions::set_reward_params::set_reward_params(
+            ctx,
+            reward_index,
+            emissions_per_second_x64,
+            end_time,
+        )
+    }
+
+    pub fn withdraw_reward_surplus(
+        ctx: Context<WithdrawRewardSurplus>,
+        reward_index: u8,
+    ) -> Result<()> {
+        instructions::withdraw_reward_surplus::withdraw_reward_surplus(ctx, reward_index)
+    }
+
+    pub fn collect_rewards<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CollectRewards<'info>>,
+    ) -> Result<()> {
+        instructions::collect_rewards::collect_rewards(ctx)
+    }
 }
 
+#[warn(deprecated)]
 impl PoolState {
     pub const LEN: usize = 8 + std::mem::size_of::<PoolState>();
 }
 
+#[warn(deprecated)]
 impl PoolState {
     pub fn initialize(
         &mut self,
+        amm_config: Pubkey,
         mint_0: &InterfaceAccount<Mint>,
         mint_1: &InterfaceAccount<Mint>,
         vault_0: Pubkey,
@@ -171,7 +482,7 @@ impl PoolState {
         current_tick: i32,
         bump: u8,
     ) -> Result<()> {
-        
+        self.amm_config = amm_config;
         self.token_mint_0 = mint_0.to_account_info().key();
         self.token_mint_1 = mint_1.to_account_info().key();
         self.token_vault_0 = vault_0;
@@ -185,20 +496,108 @@ impl PoolState {
 
         Ok(())
     }
-    
+
+    /// Whether the admin left the operation enabled
+    pub fn get_status_by_bit(&self, bit: PoolStatusBitIndex) -> bool {
+        self.status & (1 << bit as u8) == 0
+    }
+
+    /// Accrues each reward's emissions since its last update to the liquidity that was
+    /// in range meanwhile, must run before the liquidity changes. Returns the reward
+    /// growths global, which wrap like fee growth since only their differences are used
+    pub fn update_reward_infos(&mut self, curr_timestamp: u64) -> Result<[u128; REWARD_NUM]> {
+        let liquidity = self.liquidity;
+        let mut reward_growths_global_x64 = [0; REWARD_NUM];
+        for (i, reward_info) in self.reward_infos.iter_mut().enumerate() {
+            if !reward_info.initialized() {
+                continue;
+            }
+            let start_time = reward_info.last_update_time.max(reward_info.open_time);
+            let end_time = curr_timestamp.min(reward_info.end_time);
+            if liquidity != 0 && end_time > start_time {
+                let time_delta = U256::from(end_time - start_time);
+                let growth_delta = time_delta
+                    .mul_div_floor(U256::from(reward_info.emissions_per_second_x64), U256::from(liquidity))
+                    .ok_or(ClmmError::CalculateOverflow)?;
+                reward_info.reward_growth_global_x64 = reward_info
+                    .reward_growth_global_x64
+                    .wrapping_add(growth_delta.low_u128());
+                let emitted = time_delta
+                    .mul_div_floor(U256::from(reward_info.emissions_per_second_x64), U256::from(Q64))
+                    .ok_or(ClmmError::CalculateOverflow)?;
+                require_gte!(U256::from(u64::MAX), emitted, ClmmError::CalculateOverflow);
+                reward_info.reward_total_emissioned = reward_info
+                    .reward_total_emissioned
+                    .checked_add(emitted.as_u64())
+                    .ok_or(ClmmError::CalculateOverflow)?;
+            }
+            reward_info.last_update_time = reward_info.last_update_time.max(curr_timestamp);
+            reward_growths_global_x64[i] = reward_info.reward_growth_global_x64;
+        }
+        Ok(reward_growths_global_x64)
+    }
+
+    pub fn flip_tick_array_bit(
+        &mut self,
+        extension: Option<&mut TickArrayBitmapExtension>,
+        tick_array_start_index: i32,
+    ) -> Result<()> {
+        let mut tick_array_bitmap = self.tick_array_bitmap;
+        tick_array_bitmap_extension::flip_tick_array_bit(
+            &mut tick_array_bitmap,
+            extension,
+            tick_array_start_index,
+            self.tick_spacing,
+        )?;
+        self.tick_array_bitmap = tick_array_bitmap;
+        Ok(())
+    }
+
+    pub fn is_tick_array_initialized(
+        &self,
+        extension: Option<&TickArrayBitmapExtension>,
+        tick_array_start_index: i32,
+    ) -> Result<bool> {
+        tick_array_bitmap_extension::is_tick_array_initialized(
+            &{ self.tick_array_bitmap },
+            extension,
+            tick_array_start_index,
+            self.tick_spacing,
+        )
+    }
+
+    pub fn next_initialized_tick_array_start_index(
+        &self,
+        extension: &TickArrayBitmapExtension,
+        tick_array_start_index: i32,
+        zero_for_one: bool,
+    ) -> Result<Option<i32>> {
+        tick_array_bitmap_extension::next_initialized_tick_array_start_index(
+            &{ self.tick_array_bitmap },
+            extension,
+            tick_array_start_index,
+            self.tick_spacing,
+            zero_for_one,
+        )
+    }
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct CreatePool<'info> {
     /// Address paying to create the pool. Can be anyone
     #[account(mut)]
     pub pool_creator: Signer<'info>,
 
+    /// Fee tier and tick spacing the pool is created under
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
     /// Initialize an account to store the pool state
     #[account(
         init,
         seeds = [
             b"pool_seed",
+            amm_config.key().as_ref(),
             token_mint_0.key().as_ref(),
             token_mint_1.key().as_ref(),
         ],
@@ -209,6 +608,32 @@ pub struct CreatePool<'info> {
     // pub pool_state: Account<'info, PoolState>,
     pub pool_state: AccountLoader<'info, PoolState>,
 
+    /// Initialized tick arrays outside the range of the pool's own bitmap
+    #[account(
+        init,
+        seeds = [
+            POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        space = TickArrayBitmapExtension::LEN
+    )]
+    pub tick_array_bitmap: AccountLoader<'info, TickArrayBitmapExtension>,
+
+    /// Oracle observations of the pool
+    #[account(
+        init,
+        seeds = [
+            OBSERVATION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        space = ObservationState::space(OBSERVATION_CARDINALITY_INITIAL)
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
     /// Token_0 mint, the key must be smaller then token_1 mint.
     #[account(
         constraint = token_mint_0.key() < token_mint_1.key(),
@@ -246,6 +671,26 @@ pub struct CreatePool<'info> {
     )]
     pub token_vault_1: UncheckedAccount<'info>,
 
+    /// Allows token_mint_0 regardless of its extensions
+    #[account(
+        seeds = [
+            TOKEN_BADGE_SEED.as_bytes(),
+            token_mint_0.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub token_badge_0: Option<Box<Account<'info, TokenBadge>>>,
+
+    /// Allows token_mint_1 regardless of its extensions
+    #[account(
+        seeds = [
+            TOKEN_BADGE_SEED.as_bytes(),
+            token_mint_1.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub token_badge_1: Option<Box<Account<'info, TokenBadge>>>,
+
     /// Spl token program or token program 2022
     pub token_program_0: Interface<'info, TokenInterface>,
     /// Spl token program or token program 2022
@@ -256,13 +701,86 @@ pub struct CreatePool<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
+/// Bit of PoolState::status that disables each operation when set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolStatusBitIndex {
+    OpenPosition,
+    IncreaseLiquidity,
+    DecreaseLiquidity,
+    Swap,
+    Collect,
+}
+
+/// Bits of PoolState::status that map to a PoolStatusBitIndex
+pub const POOL_STATUS_MASK: u8 = (1 << (PoolStatusBitIndex::Collect as u8 + 1)) - 1;
+
+/// Maximum number of reward tokens a pool can emit at once
+pub const REWARD_NUM: usize = 3;
+pub const REWARD_VAULT_SEED: &str = "pool_reward_vault";
+
+/// Reward token emitted to a pool's in-range liquidity
+#[zero_copy(unsafe)]
+#[repr(C, packed)]
+#[derive(Default, Debug)]
+pub struct RewardInfo {
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    /// Can change the emissions and funds the vault
+    pub authority: Pubkey,
+    /// Reward tokens emitted per second, Q64.64
+    pub emissions_per_second_x64: u128,
+    /// Reward per unit of in-range liquidity since the reward started, Q64.64
+    pub reward_growth_global_x64: u128,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    /// Tokens emitted to liquidity so far, seconds with no liquidity in range emit nothing
+    /// and the authority takes them back with withdraw_reward_surplus after end_time
+    pub reward_total_emissioned: u64,
+    /// Tokens paid out to positions so far
+    pub reward_claimed: u64,
+}
+
+#[warn(deprecated)]
+impl RewardInfo {
+    pub fn initialized(&self) -> bool {
+        self.token_mint != Pubkey::default()
+    }
+
+    /// Vault balance needed to pay everything emitted but unclaimed, plus the
+    /// emissions still scheduled after `curr_timestamp`
+    pub fn required_vault_amount(&self, curr_timestamp: u64) -> Result<u64> {
+        let start_time = curr_timestamp.max(self.open_time);
+        let remaining = if self.end_time > start_time {
+            let remaining = U256::from(self.end_time - start_time)
+                .mul_div_ceil(U256::from(self.emissions_per_second_x64), U256::from(Q64))
+                .ok_or(ClmmError::CalculateOverflow)?;
+            require_gte!(U256::from(u64::MAX), remaining, ClmmError::CalculateOverflow);
+            remaining.as_u64()
+        } else {
+            0
+        };
+        Ok(self
+            .reward_total_emissioned
+            .checked_sub(self.reward_claimed)
+            .and_then(|unclaimed| unclaimed.checked_add(remaining))
+            .ok_or(ClmmError::CalculateOverflow)?)
+    }
+}
+
 #[account(zero_copy(unsafe))]
 #[repr(C, packed)]
 #[derive(Default, Debug)]
 pub struct PoolState {
     pub sqrt_price_x64: u128,
     pub liquidity: u128,
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
 
+    pub amm_config: Pubkey,
+    /// Protocol share of swap fees, withdrawn by the admin
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
     pub token_mint_0: Pubkey,
     pub token_mint_1: Pubkey,
     pub token_vault_0: Pubkey,
@@ -271,7 +789,113 @@ pub struct PoolState {
     pub open_time: u64,
     pub tick_spacing: u16,
     pub current_tick: i32,
+    /// One bit per initialized tick array, arrays -512..=511 around tick 0
+    pub tick_array_bitmap: [u64; POOL_TICK_ARRAY_BITMAP_WORDS],
+    /// Liquidity mining rewards, unused slots have a default mint
+    pub reward_infos: [RewardInfo; REWARD_NUM],
     pub bump: u8,
+    /// Operations disabled by the admin, one bit per PoolStatusBitIndex
+    pub status: u8,
+}
 
-    pub _padding: u8,
-}
\ No newline at end of file
+#[cfg(test)]
+mod pool_state_test {
+    use super::*;
+
+    /// Emits 10 tokens a second between 100 and 200 over 1000 liquidity, growth of 1/100 a second
+    fn pool_with_reward() -> PoolState {
+        let mut pool_state = PoolState {
+            liquidity: 1_000,
+            ..Default::default()
+        };
+        pool_state.reward_infos[0] = RewardInfo {
+            token_mint: Pubkey::new_unique(),
+            emissions_per_second_x64: 10 * Q64,
+            open_time: 100,
+            end_time: 200,
+            last_update_time: 100,
+            ..Default::default()
+        };
+        pool_state
+    }
+
+    fn reward_info(pool_state: &PoolState) -> RewardInfo {
+        pool_state.reward_infos[0]
+    }
+
+    #[test]
+    fn update_reward_infos_accrues_emissions_to_liquidity() {
+        let mut pool_state = pool_with_reward();
+        assert_eq!(pool_state.update_reward_infos(150).unwrap(), [Q64 / 2, 0, 0]);
+        let info = reward_info(&pool_state);
+        assert_eq!({ info.reward_growth_global_x64 }, Q64 / 2);
+        assert_eq!({ info.reward_total_emissioned }, 500);
+        assert_eq!({ info.last_update_time }, 150);
+
+        // A second update in the same second adds nothing
+        assert_eq!(pool_state.update_reward_infos(150).unwrap(), [Q64 / 2, 0, 0]);
+        assert_eq!({ reward_info(&pool_state).reward_total_emissioned }, 500);
+    }
+
+    #[test]
+    fn update_reward_infos_stops_at_end_time() {
+        let mut pool_state = pool_with_reward();
+        assert_eq!(pool_state.update_reward_infos(300).unwrap(), [Q64, 0, 0]);
+        assert_eq!({ reward_info(&pool_state).reward_total_emissioned }, 1_000);
+        assert_eq!({ reward_info(&pool_state).last_update_time }, 300);
+
+        assert_eq!(pool_state.update_reward_infos(400).unwrap(), [Q64, 0, 0]);
+        assert_eq!({ reward_info(&pool_state).reward_total_emissioned }, 1_000);
+    }
+
+    #[test]
+    fn update_reward_infos_emits_nothing_before_open_or_without_liquidity() {
+        let mut pool_state = pool_with_reward();
+        pool_state.update_reward_infos(50).unwrap();
+        assert_eq!({ reward_info(&pool_state).last_update_time }, 100);
+        assert_eq!({ reward_info(&pool_state).reward_total_emissioned }, 0);
+
+        pool_state.liquidity = 0;
+        pool_state.update_reward_infos(150).unwrap();
+        assert_eq!({ reward_info(&pool_state).reward_growth_global_x64 }, 0);
+        assert_eq!({ reward_info(&pool_state).last_update_time }, 150);
+
+        // Seconds without liquidity are skipped, not emitted later
+        pool_state.liquidity = 1_000;
+        assert_eq!(pool_state.update_reward_infos(200).unwrap(), [Q64 / 2, 0, 0]);
+        assert_eq!({ reward_info(&pool_state).reward_total_emissioned }, 500);
+    }
+
+    #[test]
+    fn update_reward_infos_skips_uninitialized_rewards() {
+        let mut pool_state = pool_with_reward();
+        pool_state.reward_infos[1].emissions_per_second_x64 = Q64;
+        pool_state.reward_infos[1].end_time = 200;
+        assert_eq!(pool_state.update_reward_infos(150).unwrap(), [Q64 / 2, 0, 0]);
+        assert_eq!({ pool_state.reward_infos[1].last_update_time }, 0);
+    }
+
+    #[test]
+    fn update_reward_infos_wraps_growth_over_dust_liquidity() {
+        let mut pool_state = pool_with_reward();
+        pool_state.liquidity = 1;
+        pool_state.reward_infos[0].reward_growth_global_x64 = u128::MAX - 10 * Q64 + 1;
+        // 10 tokens a second over one unit of liquidity is 10 * Q64 a second
+        assert_eq!(pool_state.update_reward_infos(102).unwrap(), [10 * Q64, 0, 0]);
+        assert_eq!({ reward_info(&pool_state).reward_total_emissioned }, 20);
+    }
+
+    #[test]
+    fn required_vault_amount_covers_unclaimed_and_scheduled_emissions() {
+        let mut pool_state = pool_with_reward();
+        pool_state.update_reward_infos(150).unwrap();
+        pool_state.reward_infos[0].reward_claimed = 200;
+        let info = reward_info(&pool_state);
+        assert_eq!(info.required_vault_amount(150).unwrap(), 300 + 500);
+        assert_eq!(info.required_vault_amount(250).unwrap(), 300);
+
+        pool_state.reward_infos[0].end_time = u64::MAX;
+        pool_state.reward_infos[0].emissions_per_second_x64 = u128::MAX;
+        assert!(reward_info(&pool_state).required_vault_amount(150).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 // use anchor_lang::prelude::*;
+#![allow(clippy::assign_op_pattern, clippy::manual_div_ceil, clippy::reversed_empty_ranges)]
 use uint::construct_uint;
 
 construct_uint! {
@@ -2,7 +2,6 @@ use anchor_lang::prelude::*;
 use crate::errors::ClmmError;
 use super::big_num::U256;
 use crate::libraries::tick_math::get_sqrt_price_at_tick;
-use super::precision_math;
 use super::precision_math::UnsafeMathTrait;
 
 // /// Add a signed liquidity delta to liquidity and revert if it overflows or underflows
@@ -68,7 +67,7 @@ pub fn get_delta_amount_0_unsigned(
     if result > U256::from(u64::MAX) {
         return Err(ClmmError::MaxTokenOverflow.into());
     }
-    return Ok(result.as_u64());
+    Ok(result.as_u64())
 }
 
 // /// Gets the delta amount_1 for given liquidity and price range
@@ -99,7 +98,7 @@ pub fn get_delta_amount_1_unsigned(
     if result > U256::from(u64::MAX) {
         return Err(ClmmError::MaxTokenOverflow.into());
     }
-    return Ok(result.as_u64());
+    Ok(result.as_u64())
 }
 
 // /// Helper function to get signed delta amount_0 for given liquidity and price range
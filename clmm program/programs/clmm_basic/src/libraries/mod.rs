@@ -4,6 +4,7 @@ pub mod big_num;
 pub mod precision_math;
 pub mod sqrt_price_math;
 pub mod swap_math;
+pub mod tick_array_bitmap;
 
 pub use tick_math::*;
 pub use liquidity_math::*;
@@ -1,3 +1,4 @@
+#![allow(clippy::wrong_self_convention)]
 use super::big_num::{U128, U256, U512};
 
 pub trait Downcast256 {
@@ -64,7 +65,7 @@ pub trait UnsafeMathTrait {
 
 impl UnsafeMathTrait for u64 {
     fn div_rounding_up(x: Self, y: Self) -> Self {
-        x / y + ((x % y > 0) as u64)
+        x.div_ceil(y)
     }
 }
 
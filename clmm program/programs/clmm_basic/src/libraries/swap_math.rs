@@ -3,11 +3,15 @@ use crate::libraries::liquidity_math;
 use crate::libraries::sqrt_price_math;
 use anchor_lang::prelude::*;
 
+/// Fee rates are expressed in hundredths of a bip, 10^-6
+pub const FEE_RATE_DENOMINATOR_VALUE: u32 = 1_000_000;
+
 #[derive(Default, Debug)]
 pub struct SwapStep {
     pub sqrt_price_next_x64: u128,
     pub amount_in: u64,
     pub amount_out: u64,
+    pub fee_amount: u64,
 }
 
 pub fn compute_swap_step(
@@ -15,36 +19,39 @@ pub fn compute_swap_step(
     sqrt_price_target_x64: u128,
     liquidity: u128,
     amount_remaining: u64,
+    fee_rate: u32,
     is_base_input: bool,
     zero_for_one: bool,
-    block_timestamp: u32,
 ) -> Result<SwapStep> {
     let mut swap_step = SwapStep::default();
 
     if is_base_input {
+        // The fee is taken out of the input before it moves the price
+        let amount_remaining_less_fee = (u128::from(amount_remaining)
+            * u128::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate)
+            / u128::from(FEE_RATE_DENOMINATOR_VALUE)) as u64;
+
         // Compute the theoretical input needed to reach the target price
         let amount_in = calculate_amount_in_range(
             sqrt_price_current_x64,
             sqrt_price_target_x64,
             liquidity,
-            amount_remaining,
             is_base_input,
             zero_for_one,
-            block_timestamp,
         )?;
-        if amount_in.is_some() {
-            swap_step.amount_in = amount_in.unwrap();
+        if let Some(amount_in) = amount_in {
+            swap_step.amount_in = amount_in;
         }
 
         // Determine the actual next sqrt price
         swap_step.sqrt_price_next_x64 = 
-            if amount_in.is_some() && amount_remaining >= swap_step.amount_in {
+            if amount_in.is_some() && amount_remaining_less_fee >= swap_step.amount_in {
                 sqrt_price_target_x64
             } else {
                 sqrt_price_math::get_next_sqrt_price_from_input(
                     sqrt_price_current_x64,
                     liquidity,
-                    amount_remaining,
+                    amount_remaining_less_fee,
                     zero_for_one,
                 )
             };
@@ -53,21 +60,19 @@ pub fn compute_swap_step(
             sqrt_price_current_x64,
             sqrt_price_target_x64,
             liquidity,
-            amount_remaining,
             is_base_input,
             zero_for_one,
-            block_timestamp,
         )?;
-        if amount_out.is_some() {
-            swap_step.amount_out = amount_out.unwrap();
+        if let Some(amount_out) = amount_out {
+            swap_step.amount_out = amount_out;
         }
 
-        // Determine the actual next sqrt price]
+        // Determine the actual next sqrt price
         swap_step.sqrt_price_next_x64 = 
             if amount_out.is_some() && amount_remaining >= swap_step.amount_out {
                 sqrt_price_target_x64
             } else {
-                sqrt_price_math::get_next_sqrt_price_from_input(
+                sqrt_price_math::get_next_sqrt_price_from_output(
                     sqrt_price_current_x64,
                     liquidity,
                     amount_remaining,
@@ -81,9 +86,8 @@ pub fn compute_swap_step(
 
     // Recalculate exact amounts if the target price was NOT reached
     if zero_for_one {
+        // Swapping token 0 -> token 1
         if !(max && is_base_input) {
-        // Swapping token 1 -> token 0
-
             swap_step.amount_in = liquidity_math::get_delta_amount_0_unsigned(
                 swap_step.sqrt_price_next_x64,
                 sqrt_price_current_x64,
@@ -91,7 +95,7 @@ pub fn compute_swap_step(
                 true,
             )?
         };
-        if !(max && !is_base_input) {
+        if !max || is_base_input {
             swap_step.amount_out = liquidity_math::get_delta_amount_1_unsigned(
                 swap_step.sqrt_price_next_x64,
                 sqrt_price_current_x64,
@@ -109,7 +113,7 @@ pub fn compute_swap_step(
                 true,
             )?
         };
-        if !(max && !is_base_input) {
+        if !max || is_base_input {
             swap_step.amount_out = liquidity_math::get_delta_amount_0_unsigned(
                 sqrt_price_current_x64,
                 swap_step.sqrt_price_next_x64,
@@ -123,6 +127,17 @@ pub fn compute_swap_step(
         swap_step.amount_out = amount_remaining;
     }
 
+    swap_step.fee_amount =
+        if is_base_input && swap_step.sqrt_price_next_x64 != sqrt_price_target_x64 {
+            // The target was not reached, so the rest of the input is kept as fee
+            amount_remaining - swap_step.amount_in
+        } else {
+            // Fee on top of amount_in, rounded up in favour of the pool
+            let numerator = u128::from(swap_step.amount_in) * u128::from(fee_rate);
+            let denominator = u128::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate);
+            (numerator / denominator + u128::from(numerator % denominator > 0)) as u64
+        };
+
     Ok(swap_step)
 }
 
@@ -130,10 +145,8 @@ fn calculate_amount_in_range(
     sqrt_price_current_x64: u128,
     sqrt_price_target_x64: u128,
     liquidity: u128,
-    amount_remaining: u64,
     is_base_input: bool,
     zero_for_one: bool,
-    block_timestamp: u32,
 ) -> Result<Option<u64>> {
     if is_base_input {
         let result  = if zero_for_one {
@@ -152,14 +165,10 @@ fn calculate_amount_in_range(
             )
         };
 
-        if result.is_ok() {
-            return Ok(Some(result.unwrap()));
-        } else {
-            if result.err().unwrap() == ClmmError::MaxTokenOverflow.into() {
-                return Ok(None);
-            } else {
-                return Err(ClmmError::MaxTokenOverflow.into());
-            }
+        match result {
+            Ok(amount) => Ok(Some(amount)),
+            Err(err) if err == ClmmError::MaxTokenOverflow.into() => Ok(None),
+            Err(_) => Err(ClmmError::MaxTokenOverflow.into()),
         }
     } else {
         let result  = if zero_for_one {
@@ -178,14 +187,165 @@ fn calculate_amount_in_range(
             )
         };
 
-        if result.is_ok() {
-            return Ok(Some(result.unwrap()));
-        } else {
-            if result.err().unwrap() == ClmmError::MaxTokenOverflow.into() {
-                return Ok(None);
-            } else {
-                return Err(ClmmError::MaxTokenOverflow.into());
-            }
+        match result {
+            Ok(amount) => Ok(Some(amount)),
+            Err(err) if err == ClmmError::MaxTokenOverflow.into() => Ok(None),
+            Err(_) => Err(ClmmError::MaxTokenOverflow.into()),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod swap_math_test {
+    use super::*;
+    use crate::libraries::tick_math::get_sqrt_price_at_tick;
+
+    const FEE_RATE: u32 = 3_000;
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+
+    /// Smallest fee that keeps amount_in + fee at the configured rate
+    fn fee_on_amount_in(amount_in: u64, fee_rate: u32) -> u64 {
+        let numerator = u128::from(amount_in) * u128::from(fee_rate);
+        let denominator = u128::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate);
+        numerator.div_ceil(denominator) as u64
+    }
+
+    #[test]
+    fn exact_input_takes_fee_before_moving_price() {
+        let sqrt_price_current_x64 = get_sqrt_price_at_tick(0).unwrap();
+        let sqrt_price_target_x64 = get_sqrt_price_at_tick(-10_000).unwrap();
+        let amount_remaining = 1_000_000;
+
+        let step = compute_swap_step(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            LIQUIDITY,
+            amount_remaining,
+            FEE_RATE,
+            true,
+            true,
+        )
+        .unwrap();
+
+        let amount_less_fee = amount_remaining * u64::from(FEE_RATE_DENOMINATOR_VALUE - FEE_RATE)
+            / u64::from(FEE_RATE_DENOMINATOR_VALUE);
+        assert_eq!(
+            step.sqrt_price_next_x64,
+            sqrt_price_math::get_next_sqrt_price_from_input(
+                sqrt_price_current_x64,
+                LIQUIDITY,
+                amount_less_fee,
+                true,
+            )
+        );
+        assert!(step.sqrt_price_next_x64 > sqrt_price_target_x64);
+        // The whole input is spent, whatever did not move the price is fee
+        assert_eq!(step.amount_in + step.fee_amount, amount_remaining);
+        assert!(step.amount_in <= amount_less_fee);
+        assert!(step.fee_amount >= amount_remaining - amount_less_fee);
+        assert!(step.amount_out > 0);
+    }
+
+    #[test]
+    fn exact_input_reaching_target_rounds_fee_up() {
+        let sqrt_price_current_x64 = get_sqrt_price_at_tick(0).unwrap();
+        let sqrt_price_target_x64 = get_sqrt_price_at_tick(10).unwrap();
+
+        let step = compute_swap_step(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            LIQUIDITY,
+            u64::MAX / 2,
+            FEE_RATE,
+            true,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(step.sqrt_price_next_x64, sqrt_price_target_x64);
+        assert_eq!(
+            step.amount_in,
+            liquidity_math::get_delta_amount_1_unsigned(
+                sqrt_price_current_x64,
+                sqrt_price_target_x64,
+                LIQUIDITY,
+                true,
+            )
+            .unwrap()
+        );
+        assert_eq!(step.fee_amount, fee_on_amount_in(step.amount_in, FEE_RATE));
+        // Rounded up: never below the exact fee, and less than one unit above it
+        let charged = u128::from(step.fee_amount) * u128::from(FEE_RATE_DENOMINATOR_VALUE - FEE_RATE);
+        let exact = u128::from(step.amount_in) * u128::from(FEE_RATE);
+        assert!(charged >= exact);
+        assert!(charged - exact < u128::from(FEE_RATE_DENOMINATOR_VALUE - FEE_RATE));
+    }
+
+    #[test]
+    fn exact_output_charges_fee_on_top_of_input() {
+        let sqrt_price_current_x64 = get_sqrt_price_at_tick(0).unwrap();
+        let sqrt_price_target_x64 = get_sqrt_price_at_tick(-10_000).unwrap();
+        let amount_out = 1_000_000;
+
+        let step = compute_swap_step(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            LIQUIDITY,
+            amount_out,
+            FEE_RATE,
+            false,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(step.amount_out, amount_out);
+        assert_eq!(
+            step.sqrt_price_next_x64,
+            sqrt_price_math::get_next_sqrt_price_from_output(
+                sqrt_price_current_x64,
+                LIQUIDITY,
+                amount_out,
+                true,
+            )
+        );
+        assert!(step.amount_in >= amount_out);
+        assert_eq!(step.fee_amount, fee_on_amount_in(step.amount_in, FEE_RATE));
+        assert!(step.fee_amount > 0);
+    }
+
+    #[test]
+    fn exact_output_capped_at_target() {
+        let sqrt_price_current_x64 = get_sqrt_price_at_tick(0).unwrap();
+        let sqrt_price_target_x64 = get_sqrt_price_at_tick(10).unwrap();
+
+        let step = compute_swap_step(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            LIQUIDITY,
+            u64::MAX / 2,
+            FEE_RATE,
+            false,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(step.sqrt_price_next_x64, sqrt_price_target_x64);
+        assert!(step.amount_out < u64::MAX / 2);
+        assert_eq!(step.fee_amount, fee_on_amount_in(step.amount_in, FEE_RATE));
+    }
+
+    #[test]
+    fn zero_fee_rate_charges_nothing() {
+        let step = compute_swap_step(
+            get_sqrt_price_at_tick(0).unwrap(),
+            get_sqrt_price_at_tick(10).unwrap(),
+            LIQUIDITY,
+            u64::MAX / 2,
+            0,
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(step.fee_amount, 0);
+    }
+}
@@ -0,0 +1,111 @@
+//! Bit helpers over a little-endian sequence of u64 words, bit `i` lives in
+//! word `i / 64` at position `i % 64`
+
+pub fn is_bit_set(words: &[u64], bit: usize) -> bool {
+    words[bit / 64] & (1u64 << (bit % 64)) != 0
+}
+
+pub fn flip_bit(words: &mut [u64], bit: usize) {
+    words[bit / 64] ^= 1u64 << (bit % 64);
+}
+
+/// Finds the first set bit at or after `from` (upward) or at or before `from` (downward)
+pub fn next_set_bit(words: &[u64], from: usize, upward: bool) -> Option<usize> {
+    let bit_count = words.len() * 64;
+    if from >= bit_count {
+        return if upward {
+            None
+        } else {
+            next_set_bit(words, bit_count - 1, false)
+        };
+    }
+
+    let mut word_index = from / 64;
+    let bit_in_word = from % 64;
+    if upward {
+        // Drop the bits below `from` in the first word
+        let mut word = words[word_index] & (u64::MAX << bit_in_word);
+        loop {
+            if word != 0 {
+                return Some(word_index * 64 + word.trailing_zeros() as usize);
+            }
+            word_index += 1;
+            if word_index == words.len() {
+                return None;
+            }
+            word = words[word_index];
+        }
+    } else {
+        // Drop the bits above `from` in the first word
+        let mut word = words[word_index] & (u64::MAX >> (63 - bit_in_word));
+        loop {
+            if word != 0 {
+                return Some(word_index * 64 + 63 - word.leading_zeros() as usize);
+            }
+            if word_index == 0 {
+                return None;
+            }
+            word_index -= 1;
+            word = words[word_index];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tick_array_bitmap_test {
+    use super::*;
+
+    fn words_with(bits: &[usize]) -> [u64; 4] {
+        let mut words = [0u64; 4];
+        for bit in bits {
+            flip_bit(&mut words, *bit);
+        }
+        words
+    }
+
+    #[test]
+    fn next_set_bit_includes_from() {
+        let words = words_with(&[70]);
+        assert_eq!(next_set_bit(&words, 70, true), Some(70));
+        assert_eq!(next_set_bit(&words, 70, false), Some(70));
+    }
+
+    #[test]
+    fn next_set_bit_upward_crosses_words() {
+        let words = words_with(&[3, 130]);
+        assert_eq!(next_set_bit(&words, 0, true), Some(3));
+        assert_eq!(next_set_bit(&words, 4, true), Some(130));
+        assert_eq!(next_set_bit(&words, 131, true), None);
+    }
+
+    #[test]
+    fn next_set_bit_downward_crosses_words() {
+        let words = words_with(&[3, 130]);
+        assert_eq!(next_set_bit(&words, 255, false), Some(130));
+        assert_eq!(next_set_bit(&words, 129, false), Some(3));
+        assert_eq!(next_set_bit(&words, 2, false), None);
+    }
+
+    #[test]
+    fn next_set_bit_at_word_edges() {
+        let words = words_with(&[63, 64]);
+        assert_eq!(next_set_bit(&words, 0, true), Some(63));
+        assert_eq!(next_set_bit(&words, 255, false), Some(64));
+        assert_eq!(next_set_bit(&words_with(&[0]), 255, false), Some(0));
+        assert_eq!(next_set_bit(&words_with(&[255]), 0, true), Some(255));
+    }
+
+    #[test]
+    fn next_set_bit_from_past_the_end() {
+        let words = words_with(&[200]);
+        assert_eq!(next_set_bit(&words, 256, true), None);
+        assert_eq!(next_set_bit(&words, 1_000, false), Some(200));
+    }
+
+    #[test]
+    fn next_set_bit_on_empty_words() {
+        let words = [0u64; 4];
+        assert_eq!(next_set_bit(&words, 0, true), None);
+        assert_eq!(next_set_bit(&words, 255, false), None);
+    }
+}
@@ -1,7 +1,5 @@
 use anchor_lang::prelude::*;
 
-pub type U128 = u128;
-
 pub const MIN_TICK: i32 = -443636;
 /// The minimum tick
 pub const MAX_TICK: i32 = -MIN_TICK;
@@ -16,7 +14,7 @@ const Q64: u32 = 64;
 const BIT_PRECISION: u32 = 16;
 
 pub fn get_sqrt_price_at_tick(tick: i32) -> Result<u128> {
-    let abs_tick = tick.abs() as u32;
+    let abs_tick = tick.unsigned_abs();
 
     let mut ratio: u128 = if abs_tick & 0x1 != 0 {
         0xfffcb933bd6fb800u128
@@ -69,6 +67,15 @@ pub fn get_sqrt_price_at_tick(tick: i32) -> Result<u128> {
     if abs_tick & 0x8000 != 0 {
         ratio = (ratio * 0x31be135f97ed3200u128) >> Q64;
     }
+    if abs_tick & 0x10000 != 0 {
+        ratio = (ratio * 0x9aa508b5b85a500u128) >> Q64;
+    }
+    if abs_tick & 0x20000 != 0 {
+        ratio = (ratio * 0x5d6af8dedc582cu128) >> Q64;
+    }
+    if abs_tick & 0x40000 != 0 {
+        ratio = (ratio * 0x2216e584f5fau128) >> Q64;
+    }
 
     if tick > 0 {
         ratio = u128::MAX / ratio;
@@ -106,7 +113,7 @@ pub fn get_tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
 
     while bit > 0 && precision < BIT_PRECISION {
         r *= r;
-        let is_r_more_than_two = r >> 127 as u32;
+        let is_r_more_than_two = r >> 127;
         r >>= 63 + is_r_more_than_two;
         log2p_fraction_x64 += bit * is_r_more_than_two as i128;
         bit >>= 1;
@@ -134,4 +141,36 @@ pub fn get_tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
     } else {
         tick_low
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tick_math_test {
+    use super::*;
+
+    #[test]
+    fn sqrt_price_at_tick_bounds() {
+        assert_eq!(get_sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(get_sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+        assert_eq!(get_sqrt_price_at_tick(0).unwrap(), 1u128 << 64);
+    }
+
+    #[test]
+    fn sqrt_price_at_tick_is_monotonic_past_bit_15() {
+        // Ticks beyond 2^15 need the factors for bits 16 to 18
+        let mut previous = get_sqrt_price_at_tick(MIN_TICK).unwrap();
+        for tick in (MIN_TICK..=MAX_TICK).step_by(9_973).skip(1) {
+            let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+            assert!(sqrt_price_x64 > previous, "tick {}", tick);
+            previous = sqrt_price_x64;
+        }
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_round_trips() {
+        for tick in [MIN_TICK, -300_000, -70_000, -1, 0, 1, 70_000, 300_000, MAX_TICK - 1] {
+            let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price_x64).unwrap(), tick);
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price_x64 + 1).unwrap(), tick);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+pub const AMM_CONFIG_SEED: &str = "amm_config";
+
+impl AmmConfig {
+    pub const LEN: usize = 8 + 1 + 2 + 32 + 4 + 4 + 2 + 64;
+}
+
+/// Fee tier and tick spacing shared by every pool created under it
+#[account]
+#[derive(Default, Debug)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    /// Protocol share of the trade fee, in hundredths of a bip
+    pub protocol_fee_rate: u32,
+    /// Swap fee in hundredths of a bip
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub padding: [u64; 8],
+}
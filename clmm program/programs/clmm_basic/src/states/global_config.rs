@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+pub const GLOBAL_CONFIG_SEED: &str = "global_config";
+
+impl GlobalConfig {
+    pub const LEN: usize = 8 + 1 + 32 + 32 + 64;
+}
+
+/// Program-wide settings, a single PDA holding the admin
+#[account]
+#[derive(Default, Debug)]
+pub struct GlobalConfig {
+    pub bump: u8,
+    /// Creates fee tiers and token badges, approves rewards, collects protocol fees
+    /// and pauses pools
+    pub admin: Pubkey,
+    /// Becomes the admin once it accepts, default when no transfer is pending
+    pub pending_admin: Pubkey,
+    pub padding: [u64; 8],
+}
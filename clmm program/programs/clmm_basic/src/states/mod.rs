@@ -1 +1,7 @@
-pub mod tick_array;
\ No newline at end of file
+pub mod tick_array;
+pub mod personal_position;
+pub mod config;
+pub mod tick_array_bitmap_extension;
+pub mod token_badge;
+pub mod oracle;
+pub mod global_config;
\ No newline at end of file
@@ -0,0 +1,406 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::libraries::big_num::U256;
+use std::cell::{Ref, RefMut};
+use std::mem::size_of;
+
+pub const OBSERVATION_SEED: &str = "observation";
+/// Observations a pool starts with, raised by increase_observation_cardinality_next
+pub const OBSERVATION_CARDINALITY_INITIAL: u16 = 1;
+
+impl ObservationState {
+    /// Header only, the observations follow it in the account
+    pub const LEN: usize = 8 + size_of::<ObservationState>();
+
+    /// Account size holding `cardinality` observations
+    pub fn space(cardinality: u16) -> usize {
+        Self::LEN + usize::from(cardinality) * size_of::<Observation>()
+    }
+}
+
+/// Cumulative values of a pool at one point in time. Differences between two
+/// observations give the time-weighted averages over the interval between them
+#[zero_copy]
+#[repr(C, packed)]
+#[derive(Default, Debug)]
+pub struct Observation {
+    /// Zero until the slot is first written
+    pub block_timestamp: u32,
+    /// Sum of the current tick over every elapsed second
+    pub tick_cumulative: i64,
+    /// Sum of seconds / max(1, in-range liquidity), Q64.64
+    pub seconds_per_liquidity_cumulative_x64: u128,
+}
+
+impl Observation {
+    pub fn is_initialized(&self) -> bool {
+        self.block_timestamp != 0
+    }
+
+    /// Extends this observation to `block_timestamp`, assuming tick and liquidity held
+    /// since it was taken
+    pub fn transform(&self, block_timestamp: u32, tick: i32, liquidity: u128) -> Observation {
+        let delta = block_timestamp.wrapping_sub(self.block_timestamp);
+        Observation {
+            block_timestamp,
+            tick_cumulative: self
+                .tick_cumulative
+                .wrapping_add(i64::from(tick) * i64::from(delta)),
+            seconds_per_liquidity_cumulative_x64: self
+                .seconds_per_liquidity_cumulative_x64
+                .wrapping_add((u128::from(delta) << 64) / liquidity.max(1)),
+        }
+    }
+}
+
+/// Header of a pool's observation ring buffer. The observations are stored right
+/// after it, as many as the account has room for, and are written at most once per
+/// second by swaps
+#[account(zero_copy(unsafe))]
+#[repr(C, packed)]
+pub struct ObservationState {
+    pub pool_id: Pubkey,
+    /// Index of the most recent observation
+    pub observation_index: u16,
+    /// Observations in use by the ring buffer
+    pub observation_cardinality: u16,
+    /// Observations the account has room for. The ring buffer grows into them once
+    /// it wraps around to the end of the slots in use
+    pub observation_cardinality_next: u16,
+    pub padding: [u64; 4],
+}
+
+/// Splits an observation account into its header and the observations after it
+pub fn load_observations<'a>(
+    account_info: &'a AccountInfo,
+) -> Result<(Ref<'a, ObservationState>, Ref<'a, [Observation]>)> {
+    let data = Ref::map(account_info.try_borrow_data()?, |data| &data[8..]);
+    let (state, observations) =
+        Ref::map_split(data, |data| data.split_at(size_of::<ObservationState>()));
+    Ok((
+        Ref::map(state, bytemuck::from_bytes),
+        Ref::map(observations, |observations| {
+            let len = observations.len() / size_of::<Observation>() * size_of::<Observation>();
+            bytemuck::cast_slice(&observations[..len])
+        }),
+    ))
+}
+
+pub fn load_observations_mut<'a>(
+    account_info: &'a AccountInfo,
+) -> Result<(RefMut<'a, ObservationState>, RefMut<'a, [Observation]>)> {
+    require!(account_info.is_writable, ErrorCode::AccountNotMutable);
+    let data = RefMut::map(account_info.try_borrow_mut_data()?, |data| &mut data[8..]);
+    let (state, observations) =
+        RefMut::map_split(data, |data| data.split_at_mut(size_of::<ObservationState>()));
+    Ok((
+        RefMut::map(state, bytemuck::from_bytes_mut),
+        RefMut::map(observations, |observations| {
+            let len = observations.len() / size_of::<Observation>() * size_of::<Observation>();
+            bytemuck::cast_slice_mut(&mut observations[..len])
+        }),
+    ))
+}
+
+/// Same as load_observations_mut, checking the account belongs to `pool_id`
+pub fn load_pool_observations_mut<'a>(
+    account_info: &'a AccountInfo,
+    pool_id: &Pubkey,
+) -> Result<(RefMut<'a, ObservationState>, RefMut<'a, [Observation]>)> {
+    let (observation_state, observations) = load_observations_mut(account_info)?;
+    require_keys_eq!(observation_state.pool_id, *pool_id, ClmmError::InvalidObservationState);
+    Ok((observation_state, observations))
+}
+
+impl ObservationState {
+    pub fn initialize(
+        &mut self,
+        observations: &mut [Observation],
+        pool_id: Pubkey,
+        block_timestamp: u32,
+    ) {
+        self.pool_id = pool_id;
+        self.observation_index = 0;
+        self.observation_cardinality = OBSERVATION_CARDINALITY_INITIAL;
+        self.observation_cardinality_next = OBSERVATION_CARDINALITY_INITIAL;
+        observations[0].block_timestamp = block_timestamp;
+    }
+
+    /// Records the tick and liquidity that held up to `block_timestamp`. A second write
+    /// within the same second is ignored. The clock's timestamp is fixed for a slot, so
+    /// this also caps writes at one per slot; several slots can share a second and only
+    /// the first of them writes. Keying on the second is deliberate: two observations
+    /// with the same timestamp would leave nothing to interpolate between
+    pub fn update(
+        &mut self,
+        observations: &mut [Observation],
+        block_timestamp: u32,
+        tick: i32,
+        liquidity: u128,
+    ) {
+        let observation_index = usize::from(self.observation_index);
+        let last = observations[observation_index];
+        if last.block_timestamp == block_timestamp {
+            return;
+        }
+        // Grow into the extra room only once the ring buffer reaches its last slot,
+        // so the order of the existing observations is kept
+        if self.observation_cardinality_next > self.observation_cardinality
+            && observation_index == usize::from(self.observation_cardinality) - 1
+        {
+            self.observation_cardinality = self.observation_cardinality_next;
+        }
+        let next_index = (observation_index + 1) % usize::from(self.observation_cardinality);
+        observations[next_index] = last.transform(block_timestamp, tick, liquidity);
+        self.observation_index = next_index as u16;
+    }
+
+    /// Tick and seconds-per-liquidity cumulatives as of `seconds_ago` before
+    /// `block_timestamp`, interpolated between the observations around that time
+    pub fn observe_single(
+        &self,
+        observations: &[Observation],
+        block_timestamp: u32,
+        seconds_ago: u32,
+        tick: i32,
+        liquidity: u128,
+    ) -> Result<(i64, u128)> {
+        let target = block_timestamp
+            .checked_sub(seconds_ago)
+            .ok_or(ClmmError::ObservationTooOld)?;
+        let newest = observations[usize::from(self.observation_index)];
+        if newest.block_timestamp <= target {
+            let observation = if newest.block_timestamp == target {
+                newest
+            } else {
+                newest.transform(target, tick, liquidity)
+            };
+            return Ok((
+                observation.tick_cumulative,
+                observation.seconds_per_liquidity_cumulative_x64,
+            ));
+        }
+
+        let (before_or_at, at_or_after) = self.surrounding_observations(observations, target)?;
+        if target == before_or_at.block_timestamp {
+            return Ok((
+                before_or_at.tick_cumulative,
+                before_or_at.seconds_per_liquidity_cumulative_x64,
+            ));
+        }
+        if target == at_or_after.block_timestamp {
+            return Ok((
+                at_or_after.tick_cumulative,
+                at_or_after.seconds_per_liquidity_cumulative_x64,
+            ));
+        }
+
+        let observation_time_delta = at_or_after
+            .block_timestamp
+            .wrapping_sub(before_or_at.block_timestamp);
+        let target_delta = target.wrapping_sub(before_or_at.block_timestamp);
+        let tick_cumulative = before_or_at.tick_cumulative.wrapping_add(
+            at_or_after
+                .tick_cumulative
+                .wrapping_sub(before_or_at.tick_cumulative)
+                / i64::from(observation_time_delta)
+                * i64::from(target_delta),
+        );
+        let seconds_per_liquidity_cumulative_x64 = before_or_at
+            .seconds_per_liquidity_cumulative_x64
+            .wrapping_add(
+                U256::from(
+                    at_or_after
+                        .seconds_per_liquidity_cumulative_x64
+                        .wrapping_sub(before_or_at.seconds_per_liquidity_cumulative_x64),
+                )
+                .mul_div_floor(U256::from(target_delta), U256::from(observation_time_delta))
+                .ok_or(ClmmError::CalculateOverflow)?
+                .as_u128(),
+            );
+        Ok((tick_cumulative, seconds_per_liquidity_cumulative_x64))
+    }
+
+    /// Binary search for the observations at or around `target`, which must lie
+    /// between the oldest observation and the newest one
+    fn surrounding_observations(
+        &self,
+        observations: &[Observation],
+        target: u32,
+    ) -> Result<(Observation, Observation)> {
+        let cardinality = usize::from(self.observation_cardinality);
+        // Slots the ring buffer just grew into stay empty until it wraps around to them
+        let mut oldest_index = (usize::from(self.observation_index) + 1) % cardinality;
+        if !observations[oldest_index].is_initialized() {
+            oldest_index = 0;
+        }
+        require_gte!(
+            target,
+            observations[oldest_index].block_timestamp,
+            ClmmError::ObservationTooOld
+        );
+
+        // Search over positions counted from the oldest observation
+        let mut left = oldest_index;
+        let mut right = usize::from(self.observation_index) + cardinality;
+        if right >= left + cardinality {
+            right -= cardinality;
+        }
+        loop {
+            let middle = (left + right) / 2;
+            let before_or_at = observations[middle % cardinality];
+            let at_or_after = observations[(middle + 1) % cardinality];
+            if before_or_at.block_timestamp > target {
+                right = middle - 1;
+            } else if at_or_after.block_timestamp < target {
+                left = middle + 1;
+            } else {
+                return Ok((before_or_at, at_or_after));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod oracle_test {
+    use super::*;
+
+    const LIQUIDITY: u128 = 1_000_000;
+
+    fn observation_state(cardinality_next: u16) -> (ObservationState, Vec<Observation>) {
+        let mut observation_state = ObservationState {
+            pool_id: Pubkey::default(),
+            observation_index: 0,
+            observation_cardinality: 0,
+            observation_cardinality_next: 0,
+            padding: [0; 4],
+        };
+        let mut observations = vec![Observation::default(); usize::from(cardinality_next)];
+        observation_state.initialize(&mut observations, Pubkey::default(), 100);
+        observation_state.observation_cardinality_next = cardinality_next;
+        (observation_state, observations)
+    }
+
+    fn timestamps(observations: &[Observation]) -> Vec<u32> {
+        observations.iter().map(|observation| observation.block_timestamp).collect()
+    }
+
+    #[test]
+    fn update_writes_once_per_second() {
+        let (mut observation_state, mut observations) = observation_state(1);
+        observation_state.update(&mut observations, 100, 5, LIQUIDITY);
+        assert_eq!({ observations[0].tick_cumulative }, 0);
+
+        observation_state.update(&mut observations, 110, 5, LIQUIDITY);
+        observation_state.update(&mut observations, 110, -50, LIQUIDITY);
+        assert_eq!({ observations[0].block_timestamp }, 110);
+        assert_eq!({ observations[0].tick_cumulative }, 50);
+    }
+
+    #[test]
+    fn ring_buffer_wraps_around() {
+        let (mut observation_state, mut observations) = observation_state(3);
+        for block_timestamp in [110, 120, 130] {
+            observation_state.update(&mut observations, block_timestamp, 1, LIQUIDITY);
+        }
+        assert_eq!({ observation_state.observation_cardinality }, 3);
+        assert_eq!({ observation_state.observation_index }, 0);
+        assert_eq!(timestamps(&observations), vec![130, 110, 120]);
+    }
+
+    #[test]
+    fn ring_buffer_grows_only_from_its_last_slot() {
+        let (mut observation_state, mut observations) = observation_state(3);
+        for block_timestamp in [110, 120, 130] {
+            observation_state.update(&mut observations, block_timestamp, 1, LIQUIDITY);
+        }
+        observation_state.observation_cardinality_next = 5;
+        observations.resize(5, Observation::default());
+
+        // Slots 1 and 2 are overwritten in order before the buffer grows
+        observation_state.update(&mut observations, 140, 1, LIQUIDITY);
+        observation_state.update(&mut observations, 150, 1, LIQUIDITY);
+        assert_eq!({ observation_state.observation_cardinality }, 3);
+        assert_eq!(timestamps(&observations), vec![130, 140, 150, 0, 0]);
+
+        observation_state.update(&mut observations, 160, 1, LIQUIDITY);
+        assert_eq!({ observation_state.observation_cardinality }, 5);
+        assert_eq!({ observation_state.observation_index }, 3);
+        assert_eq!(timestamps(&observations), vec![130, 140, 150, 160, 0]);
+
+        // The oldest observation is found at slot 0 while slot 4 is still empty
+        let (before_or_at, at_or_after) =
+            observation_state.surrounding_observations(&observations, 135).unwrap();
+        assert_eq!((before_or_at.block_timestamp, at_or_after.block_timestamp), (130, 140));
+    }
+
+    #[test]
+    fn surrounding_observations_across_the_wrap() {
+        let (mut observation_state, mut observations) = observation_state(4);
+        for block_timestamp in [110, 120, 130, 140, 150] {
+            observation_state.update(&mut observations, block_timestamp, 1, LIQUIDITY);
+        }
+        assert_eq!(timestamps(&observations), vec![140, 150, 120, 130]);
+
+        let surrounding = |target| {
+            let (before_or_at, at_or_after) =
+                observation_state.surrounding_observations(&observations, target).unwrap();
+            (before_or_at.block_timestamp, at_or_after.block_timestamp)
+        };
+        assert_eq!(surrounding(120), (120, 130));
+        assert_eq!(surrounding(125), (120, 130));
+        assert_eq!(surrounding(135), (130, 140));
+        assert_eq!(surrounding(145), (140, 150));
+        // An exact hit may come back on either side of the pair
+        let (before_or_at, at_or_after) = surrounding(140);
+        assert!(before_or_at == 140 || at_or_after == 140);
+
+        assert_eq!(
+            observation_state.surrounding_observations(&observations, 119).unwrap_err(),
+            ClmmError::ObservationTooOld.into()
+        );
+    }
+
+    #[test]
+    fn observe_single_interpolates_between_observations() {
+        let (mut observation_state, mut observations) = observation_state(4);
+        // Tick 10 over [100, 110), then tick -20 over [110, 130)
+        observation_state.update(&mut observations, 110, 10, LIQUIDITY);
+        observation_state.update(&mut observations, 130, -20, LIQUIDITY);
+
+        let observe = |seconds_ago| {
+            observation_state
+                .observe_single(&observations, 130, seconds_ago, -20, LIQUIDITY)
+                .unwrap()
+        };
+        let seconds_per_liquidity_x64 = |seconds: u128| (seconds << 64) / LIQUIDITY;
+
+        assert_eq!(observe(30), (0, 0));
+        assert_eq!(observe(25), (50, seconds_per_liquidity_x64(10) / 2));
+        assert_eq!(observe(20), (100, seconds_per_liquidity_x64(10)));
+        assert_eq!(
+            observe(10),
+            (-100, seconds_per_liquidity_x64(10) + seconds_per_liquidity_x64(20) / 2)
+        );
+        assert_eq!(observe(0).0, -300);
+    }
+
+    #[test]
+    fn observe_single_extends_the_newest_observation() {
+        let (mut observation_state, mut observations) = observation_state(2);
+        observation_state.update(&mut observations, 110, 10, LIQUIDITY);
+
+        // The current tick has held since the newest observation
+        let (tick_cumulative, _) = observation_state
+            .observe_single(&observations, 125, 0, 7, LIQUIDITY)
+            .unwrap();
+        assert_eq!(tick_cumulative, 100 + 7 * 15);
+
+        assert_eq!(
+            observation_state
+                .observe_single(&observations, 125, 26, 7, LIQUIDITY)
+                .unwrap_err(),
+            ClmmError::ObservationTooOld.into()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::libraries::big_num::U256;
+use crate::libraries::liquidity_math::{add_delta, Q64};
+use crate::REWARD_NUM;
+
+pub const POSITION_SEED: &str = "position";
+
+/// How ownership of a position is represented on chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PositionKind {
+    /// Supply-1 SPL token with Metaplex metadata, `nft_mint` is its mint
+    #[default]
+    SplNft,
+    /// Metaplex Core asset, `nft_mint` is the asset address
+    CoreAsset,
+}
+
+impl PersonalPositionState {
+    pub const LEN: usize =
+        8 + 1 + 1 + 32 + 32 + 4 + 4 + 16 + 16 + 16 + 8 + 8 + (16 + 8) * REWARD_NUM + 64;
+
+    pub fn initialize(
+        &mut self,
+        bump: u8,
+        position_kind: PositionKind,
+        nft_mint: Pubkey,
+        pool_id: Pubkey,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    ) -> Result<()> {
+        self.bump = bump;
+        self.position_kind = position_kind;
+        self.nft_mint = nft_mint;
+        self.pool_id = pool_id;
+        self.tick_lower_index = tick_lower_index;
+        self.tick_upper_index = tick_upper_index;
+        self.liquidity = 0;
+        Ok(())
+    }
+
+    pub fn update_liquidity(&mut self, liquidity_delta: i128) -> Result<()> {
+        if liquidity_delta < 0 {
+            require_gte!(
+                self.liquidity,
+                u128::try_from(-liquidity_delta).unwrap(),
+                ClmmError::InvalidLiquidity
+            );
+        }
+        self.liquidity = add_delta(self.liquidity, liquidity_delta)?;
+        Ok(())
+    }
+
+    /// Accrues fees earned by the current liquidity since the last update,
+    /// must run before the liquidity is changed. Owed amounts saturate rather than
+    /// fail, a position stuck at u64::MAX can still be collected and closed
+    pub fn update_fees(
+        &mut self,
+        fee_growth_inside_0_x64: u128,
+        fee_growth_inside_1_x64: u128,
+    ) -> Result<()> {
+        self.tokens_owed_0 = self
+            .tokens_owed_0
+            .saturating_add(calculate_fees_owed(
+                fee_growth_inside_0_x64,
+                self.fee_growth_inside_0_last_x64,
+                self.liquidity,
+            ));
+        self.tokens_owed_1 = self
+            .tokens_owed_1
+            .saturating_add(calculate_fees_owed(
+                fee_growth_inside_1_x64,
+                self.fee_growth_inside_1_last_x64,
+                self.liquidity,
+            ));
+
+        self.fee_growth_inside_0_last_x64 = fee_growth_inside_0_x64;
+        self.fee_growth_inside_1_last_x64 = fee_growth_inside_1_x64;
+        Ok(())
+    }
+
+    /// Accrues rewards earned by the current liquidity since the last update,
+    /// must run before the liquidity is changed
+    pub fn update_rewards(&mut self, reward_growths_inside_x64: [u128; REWARD_NUM]) -> Result<()> {
+        let liquidity = self.liquidity;
+        for (reward_info, reward_growth_inside_x64) in
+            self.reward_infos.iter_mut().zip(reward_growths_inside_x64)
+        {
+            reward_info.reward_amount_owed = reward_info
+                .reward_amount_owed
+                .saturating_add(calculate_fees_owed(
+                    reward_growth_inside_x64,
+                    reward_info.growth_inside_last_x64,
+                    liquidity,
+                ));
+            reward_info.growth_inside_last_x64 = reward_growth_inside_x64;
+        }
+        Ok(())
+    }
+}
+
+fn calculate_fees_owed(
+    fee_growth_inside_x64: u128,
+    fee_growth_inside_last_x64: u128,
+    liquidity: u128,
+) -> u64 {
+    let fees = U256::from(fee_growth_inside_x64.wrapping_sub(fee_growth_inside_last_x64))
+        .mul_div_floor(U256::from(liquidity), U256::from(Q64))
+        .unwrap();
+    if fees > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        fees.as_u64()
+    }
+}
+
+/// Reward accounting of a position for one of the pool's rewards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PositionRewardInfo {
+    pub growth_inside_last_x64: u128,
+    pub reward_amount_owed: u64,
+}
+
+/// Liquidity inside one tick range of a pool, owned by the holder of `nft_mint`
+#[account]
+#[derive(Default, Debug)]
+pub struct PersonalPositionState {
+    pub bump: u8,
+    pub position_kind: PositionKind,
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x64: u128,
+    pub fee_growth_inside_1_last_x64: u128,
+    pub tokens_owed_0: u64,
+    pub tokens_owed_1: u64,
+    pub reward_infos: [PositionRewardInfo; REWARD_NUM],
+    pub padding: [u64; 8],
+}
+
+#[cfg(test)]
+mod personal_position_test {
+    use super::*;
+
+    #[test]
+    fn update_fees_accrues_growth_since_the_last_update() {
+        let mut position = PersonalPositionState {
+            liquidity: 2_000,
+            fee_growth_inside_0_last_x64: 5 << 64,
+            ..Default::default()
+        };
+        position.update_fees(8 << 64, 1 << 63).unwrap();
+        assert_eq!(position.tokens_owed_0, 6_000);
+        assert_eq!(position.tokens_owed_1, 1_000);
+        assert_eq!(position.fee_growth_inside_0_last_x64, 8 << 64);
+
+        // Nothing more until the growth moves again
+        position.update_fees(8 << 64, 1 << 63).unwrap();
+        assert_eq!(position.tokens_owed_0, 6_000);
+    }
+
+    #[test]
+    fn update_fees_handles_wrapped_growth_and_saturates() {
+        let mut position = PersonalPositionState {
+            liquidity: 1,
+            fee_growth_inside_0_last_x64: u128::MAX - (1 << 64) + 1,
+            ..Default::default()
+        };
+        position.update_fees(1 << 64, 0).unwrap();
+        assert_eq!(position.tokens_owed_0, 2);
+
+        let mut position = PersonalPositionState {
+            liquidity: u128::MAX,
+            ..Default::default()
+        };
+        position.update_fees(u128::MAX, 0).unwrap();
+        assert_eq!(position.tokens_owed_0, u64::MAX);
+
+        // Owed amounts stop at the limit so the position can still be collected and closed
+        position.update_fees(0, 1 << 64).unwrap();
+        assert_eq!(position.tokens_owed_0, u64::MAX);
+        position.update_rewards([1 << 64, 0, 0]).unwrap();
+        position.update_rewards([2 << 64, 0, 0]).unwrap();
+        assert_eq!(position.reward_infos[0].reward_amount_owed, u64::MAX);
+    }
+
+    #[test]
+    fn update_rewards_accrues_each_reward_since_the_last_update() {
+        let mut position = PersonalPositionState {
+            liquidity: 2_000,
+            ..Default::default()
+        };
+        position.reward_infos[0].growth_inside_last_x64 = 1 << 64;
+        position.update_rewards([3 << 64, 1 << 63, 0]).unwrap();
+        let owed = position.reward_infos.map(|reward_info| reward_info.reward_amount_owed);
+        assert_eq!(owed, [4_000, 1_000, 0]);
+        assert_eq!(position.reward_infos[0].growth_inside_last_x64, 3 << 64);
+
+        position.update_rewards([4 << 64, 1 << 63, 0]).unwrap();
+        assert_eq!(position.reward_infos[0].reward_amount_owed, 6_000);
+        assert_eq!(position.reward_infos[1].reward_amount_owed, 1_000);
+    }
+}
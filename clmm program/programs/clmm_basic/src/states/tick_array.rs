@@ -1,10 +1,10 @@
 use anchor_lang::{prelude::*, system_program};
-use crate::PoolState;
+use crate::{PoolState, REWARD_NUM};
 use crate::libraries::liquidity_math::add_delta;
 use crate::util::account_load::AccountLoad;
-use crate::util::*;
 use crate::errors::*;
 
+pub const TICK_ARRAY_SEED: &str = "tick_array";
 pub const TICK_ARRAY_SIZE_USIZE: usize = 60;
 pub const TICK_ARRAY_SIZE: i32 = 60;
 
@@ -13,7 +13,13 @@ impl TickState {
         4 +
         16 +
         16 +
-        52;
+        16 +
+        16 +
+        16 +
+        8 +
+        4 +
+        16 * REWARD_NUM +
+        20;
 
     pub fn is_initialized(&self) -> bool {
         self.liquidity_gross != 0
@@ -23,6 +29,12 @@ impl TickState {
         &mut self,
         tick_current: i32,
         liquidity_delta: i128,
+        fee_growth_global_0_x64: u128,
+        fee_growth_global_1_x64: u128,
+        seconds_per_liquidity_cumulative_x64: u128,
+        tick_cumulative: i64,
+        block_timestamp: u32,
+        reward_growths_global_x64: [u128; REWARD_NUM],
         upper: bool,
     ) -> Result<bool> {
         let liquidity_gross_before = self.liquidity_gross;
@@ -30,6 +42,16 @@ impl TickState {
 
         let flipped = (liquidity_gross_after == 0) != (liquidity_gross_before == 0);
 
+        // By convention, all growth before a tick was initialized happened below it
+        if liquidity_gross_before == 0 && self.tick <= tick_current {
+            self.fee_growth_outside_0_x64 = fee_growth_global_0_x64;
+            self.fee_growth_outside_1_x64 = fee_growth_global_1_x64;
+            self.seconds_per_liquidity_outside_x64 = seconds_per_liquidity_cumulative_x64;
+            self.tick_cumulative_outside = tick_cumulative;
+            self.seconds_outside = block_timestamp;
+            self.reward_growths_outside_x64 = reward_growths_global_x64;
+        }
+
         self.liquidity_gross = liquidity_gross_after;
 
         self.liquidity_net = if upper {
@@ -41,14 +63,162 @@ impl TickState {
         Ok(flipped)
     }
 
+    /// Flips the growth tracked outside this tick when a swap crosses it and
+    /// returns the liquidity_net to apply
+    pub fn cross(
+        &mut self,
+        fee_growth_global_0_x64: u128,
+        fee_growth_global_1_x64: u128,
+        seconds_per_liquidity_cumulative_x64: u128,
+        tick_cumulative: i64,
+        block_timestamp: u32,
+        reward_growths_global_x64: [u128; REWARD_NUM],
+    ) -> i128 {
+        self.fee_growth_outside_0_x64 = fee_growth_global_0_x64
+            .wrapping_sub(self.fee_growth_outside_0_x64);
+        self.fee_growth_outside_1_x64 = fee_growth_global_1_x64
+            .wrapping_sub(self.fee_growth_outside_1_x64);
+        self.seconds_per_liquidity_outside_x64 = seconds_per_liquidity_cumulative_x64
+            .wrapping_sub(self.seconds_per_liquidity_outside_x64);
+        self.tick_cumulative_outside = tick_cumulative.wrapping_sub(self.tick_cumulative_outside);
+        self.seconds_outside = block_timestamp.wrapping_sub(self.seconds_outside);
+        let mut reward_growths_outside_x64 = self.reward_growths_outside_x64;
+        for (reward_growth_outside_x64, reward_growth_global_x64) in
+            reward_growths_outside_x64.iter_mut().zip(reward_growths_global_x64)
+        {
+            *reward_growth_outside_x64 =
+                reward_growth_global_x64.wrapping_sub(*reward_growth_outside_x64);
+        }
+        self.reward_growths_outside_x64 = reward_growths_outside_x64;
+        self.liquidity_net
+    }
+
     pub fn clear(&mut self) {
         self.liquidity_net = 0;
         self.liquidity_gross = 0;
+        self.fee_growth_outside_0_x64 = 0;
+        self.fee_growth_outside_1_x64 = 0;
+        self.seconds_per_liquidity_outside_x64 = 0;
+        self.tick_cumulative_outside = 0;
+        self.seconds_outside = 0;
+        self.reward_growths_outside_x64 = [0; REWARD_NUM];
+    }
+}
+
+/// Fee growth per unit of liquidity between tick_lower and tick_upper
+pub fn get_fee_growth_inside(
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+    tick_current: i32,
+    fee_growth_global_0_x64: u128,
+    fee_growth_global_1_x64: u128,
+) -> (u128, u128) {
+    let (fee_growth_below_0_x64, fee_growth_below_1_x64) = if tick_current >= tick_lower.tick {
+        (
+            tick_lower.fee_growth_outside_0_x64,
+            tick_lower.fee_growth_outside_1_x64,
+        )
+    } else {
+        (
+            fee_growth_global_0_x64.wrapping_sub(tick_lower.fee_growth_outside_0_x64),
+            fee_growth_global_1_x64.wrapping_sub(tick_lower.fee_growth_outside_1_x64),
+        )
+    };
+
+    let (fee_growth_above_0_x64, fee_growth_above_1_x64) = if tick_current < tick_upper.tick {
+        (
+            tick_upper.fee_growth_outside_0_x64,
+            tick_upper.fee_growth_outside_1_x64,
+        )
+    } else {
+        (
+            fee_growth_global_0_x64.wrapping_sub(tick_upper.fee_growth_outside_0_x64),
+            fee_growth_global_1_x64.wrapping_sub(tick_upper.fee_growth_outside_1_x64),
+        )
+    };
+
+    let fee_growth_inside_0_x64 = fee_growth_global_0_x64
+        .wrapping_sub(fee_growth_below_0_x64)
+        .wrapping_sub(fee_growth_above_0_x64);
+    let fee_growth_inside_1_x64 = fee_growth_global_1_x64
+        .wrapping_sub(fee_growth_below_1_x64)
+        .wrapping_sub(fee_growth_above_1_x64);
+
+    (fee_growth_inside_0_x64, fee_growth_inside_1_x64)
+}
+
+/// Reward growth per unit of liquidity between tick_lower and tick_upper, per reward
+pub fn get_reward_growths_inside(
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+    tick_current: i32,
+    reward_growths_global_x64: [u128; REWARD_NUM],
+) -> [u128; REWARD_NUM] {
+    let lower_outside_x64 = tick_lower.reward_growths_outside_x64;
+    let upper_outside_x64 = tick_upper.reward_growths_outside_x64;
+    let mut reward_growths_inside_x64 = [0; REWARD_NUM];
+    for i in 0..REWARD_NUM {
+        let reward_growth_below_x64 = if tick_current >= tick_lower.tick {
+            lower_outside_x64[i]
+        } else {
+            reward_growths_global_x64[i].wrapping_sub(lower_outside_x64[i])
+        };
+        let reward_growth_above_x64 = if tick_current < tick_upper.tick {
+            upper_outside_x64[i]
+        } else {
+            reward_growths_global_x64[i].wrapping_sub(upper_outside_x64[i])
+        };
+        reward_growths_inside_x64[i] = reward_growths_global_x64[i]
+            .wrapping_sub(reward_growth_below_x64)
+            .wrapping_sub(reward_growth_above_x64);
+    }
+    reward_growths_inside_x64
+}
+
+/// Tick cumulative, seconds per liquidity and seconds accumulated while the current
+/// tick was between tick_lower and tick_upper. Only differences between two
+/// snapshots of the same range are meaningful
+pub fn get_cumulatives_inside(
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+    tick_current: i32,
+    tick_cumulative: i64,
+    seconds_per_liquidity_cumulative_x64: u128,
+    block_timestamp: u32,
+) -> (i64, u128, u32) {
+    if tick_current < tick_lower.tick {
+        (
+            tick_lower.tick_cumulative_outside.wrapping_sub(tick_upper.tick_cumulative_outside),
+            tick_lower
+                .seconds_per_liquidity_outside_x64
+                .wrapping_sub(tick_upper.seconds_per_liquidity_outside_x64),
+            tick_lower.seconds_outside.wrapping_sub(tick_upper.seconds_outside),
+        )
+    } else if tick_current < tick_upper.tick {
+        (
+            tick_cumulative
+                .wrapping_sub(tick_lower.tick_cumulative_outside)
+                .wrapping_sub(tick_upper.tick_cumulative_outside),
+            seconds_per_liquidity_cumulative_x64
+                .wrapping_sub(tick_lower.seconds_per_liquidity_outside_x64)
+                .wrapping_sub(tick_upper.seconds_per_liquidity_outside_x64),
+            block_timestamp
+                .wrapping_sub(tick_lower.seconds_outside)
+                .wrapping_sub(tick_upper.seconds_outside),
+        )
+    } else {
+        (
+            tick_upper.tick_cumulative_outside.wrapping_sub(tick_lower.tick_cumulative_outside),
+            tick_upper
+                .seconds_per_liquidity_outside_x64
+                .wrapping_sub(tick_lower.seconds_per_liquidity_outside_x64),
+            tick_upper.seconds_outside.wrapping_sub(tick_lower.seconds_outside),
+        )
     }
 }
 
 impl TickArrayState {
-    pub const LEN: usize = 8 + 32 + 4 + TickState::LEN * TICK_ARRAY_SIZE_USIZE + 1 + 8 + 107 + 8;
+    pub const LEN: usize = 8 + 32 + 4 + TickState::LEN * TICK_ARRAY_SIZE_USIZE + 1 + 8 + 32 + 75 + 8;
 
     pub fn get_or_create_tick_array<'info>(
         payer: AccountInfo<'info>,
@@ -56,19 +226,19 @@ impl TickArrayState {
         system_program: AccountInfo<'info>,
         pool_state_loader: &AccountLoader<'info, PoolState>,
         tick_array_start_index: i32,
-        tick_spacing: u16,
     ) -> Result<AccountLoad<'info, TickArrayState>> {
     
     let tick_array_state = if tick_array_account_info.owner == &system_program::ID {
         let (expected_pda, bump) = Pubkey::find_program_address(
             &[
-                b"tick_array",
+                TICK_ARRAY_SEED.as_bytes(),
                 pool_state_loader.key().as_ref(),
                 &tick_array_start_index.to_be_bytes(),
             ],
             &crate::id(),
         );
         require_keys_eq!(expected_pda, tick_array_account_info.key());
+        let rent_payer = payer.key();
         
         create_or_allocate_account(
             &crate::id(),
@@ -76,7 +246,7 @@ impl TickArrayState {
             system_program,
             tick_array_account_info.clone(),
             &[
-                b"tick_array",
+                TICK_ARRAY_SEED.as_bytes(),
                 pool_state_loader.key().as_ref(),
                 &tick_array_start_index.to_be_bytes(),
                 &[bump],
@@ -91,8 +261,8 @@ impl TickArrayState {
             let mut tick_array_account = tick_array_state_loader.load_init()?;
             tick_array_account.initialize(
                 tick_array_start_index,
-                tick_spacing,
                 pool_state_loader.key(),
+                rent_payer,
             )?;
         }
         tick_array_state_loader
@@ -111,6 +281,11 @@ impl TickArrayState {
         Ok(&mut self.ticks[offset_in_array])
     }
 
+    pub fn get_tick_state(&self, tick_index: i32, tick_spacing: u16) -> Result<&TickState> {
+        let offset_in_array = self.get_tick_offset_in_array(tick_index, tick_spacing)?;
+        Ok(&self.ticks[offset_in_array])
+    }
+
     pub fn get_tick_offset_in_array(&self, tick_index: i32, tick_spacing: u16) -> Result<usize> {
         let start_tick_index = TickArrayState::get_array_start_index(tick_index, tick_spacing);
         require_eq!(
@@ -127,7 +302,7 @@ impl TickArrayState {
         let ticks_in_array = Self::tick_count(tick_spacing);
         let mut start = tick_index / ticks_in_array;
         if tick_index < 0 && tick_index % ticks_in_array != 0 {
-            start = start - 1
+            start -= 1
         }
         start * ticks_in_array
     }
@@ -150,13 +325,15 @@ impl TickArrayState {
     }
 
 
-    pub fn update_initialized_tick_count(&mut self, add: bool) -> Result<()> {
+    /// Returns true when the array flips between holding no initialized ticks and holding some
+    pub fn update_initialized_tick_count(&mut self, add: bool) -> Result<bool> {
         if add {
             self.initialized_tick_count += 1;
+            Ok(self.initialized_tick_count == 1)
         } else {
             self.initialized_tick_count -= 1;
+            Ok(self.initialized_tick_count == 0)
         }
-        Ok(())
     }
 
     pub fn next_initialized_tick(
@@ -165,16 +342,32 @@ impl TickArrayState {
         tick_spacing: u16,
         zero_for_one: bool,
     ) -> Result<Option<&TickState>> {
+        // `tick` may lie outside this array when the swap skipped ahead to it
+        let tick_count = Self::tick_count(tick_spacing);
         if zero_for_one {
-            let curr_offset = self.get_tick_offset_in_array(tick, tick_spacing)?;
-            for i in (0..curr_offset).rev() {
+            if tick < self.start_tick_index {
+                return Ok(None);
+            }
+            let curr_offset = if tick >= self.start_tick_index + tick_count {
+                TICK_ARRAY_SIZE_USIZE - 1
+            } else {
+                self.get_tick_offset_in_array(tick, tick_spacing)?
+            };
+            for i in (0..=curr_offset).rev() {
                 if self.ticks[i].is_initialized() {
                     return Ok(Some(&self.ticks[i]));
                 }
             }
         } else {
-            let curr_offset = self.get_tick_offset_in_array(tick, tick_spacing)?;
-            for i in (curr_offset + 1)..TICK_ARRAY_SIZE_USIZE {
+            if tick >= self.start_tick_index + tick_count {
+                return Ok(None);
+            }
+            let first_offset = if tick < self.start_tick_index {
+                0
+            } else {
+                self.get_tick_offset_in_array(tick, tick_spacing)? + 1
+            };
+            for i in first_offset..TICK_ARRAY_SIZE_USIZE {
                 if self.ticks[i].is_initialized() {
                     return Ok(Some(&self.ticks[i]));
                 }
@@ -193,7 +386,9 @@ pub struct TickArrayState {
     pub ticks: [TickState; TICK_ARRAY_SIZE_USIZE],
     pub initialized_tick_count: u8,
     pub recent_epoch: u64,
-    pub padding: [u8; 107],
+    /// Paid the rent for this account, refunded by close_tick_array
+    pub rent_payer: Pubkey,
+    pub padding: [u8; 75],
 }
 
 
@@ -202,11 +397,12 @@ impl TickArrayState {
 pub fn initialize(
     &mut self,
     start_index: i32,
-    tick_spacing: u16,
     pool_key: Pubkey,
+    rent_payer: Pubkey,
 ) -> Result<()> {
     self.start_tick_index = start_index;
     self.pool_id = pool_key;
+    self.rent_payer = rent_payer;
     self.recent_epoch = Clock::get()?.epoch;
     Ok(())
 }
@@ -218,7 +414,18 @@ pub struct TickState {
     pub tick: i32,
     pub liquidity_net: i128,
     pub liquidity_gross: u128,
-    pub padding: [u32; 13],
+    /// Fee growth per unit of liquidity on the other side of this tick from the current tick
+    pub fee_growth_outside_0_x64: u128,
+    pub fee_growth_outside_1_x64: u128,
+    /// Seconds per unit of liquidity on the other side of this tick, Q64.64
+    pub seconds_per_liquidity_outside_x64: u128,
+    /// Tick cumulative on the other side of this tick
+    pub tick_cumulative_outside: i64,
+    /// Seconds spent on the other side of this tick
+    pub seconds_outside: u32,
+    /// Reward growth per unit of liquidity on the other side of this tick, per reward
+    pub reward_growths_outside_x64: [u128; REWARD_NUM],
+    pub padding: [u32; 5],
 }
 
 pub fn create_or_allocate_account<'a>(
@@ -276,25 +483,251 @@ pub fn create_or_allocate_account<'a>(
     Ok(())
 }
 
+#[cfg(test)]
+mod tick_array_test {
+    use super::*;
+
+    const LIQUIDITY: i128 = 1_000;
+
+    fn tick_state(tick: i32) -> TickState {
+        TickState {
+            tick,
+            liquidity_net: 0,
+            liquidity_gross: 0,
+            fee_growth_outside_0_x64: 0,
+            fee_growth_outside_1_x64: 0,
+            seconds_per_liquidity_outside_x64: 0,
+            tick_cumulative_outside: 0,
+            seconds_outside: 0,
+            reward_growths_outside_x64: [0; REWARD_NUM],
+            padding: [0; 5],
+        }
+    }
 
-pub fn update(
-    tick_state: &mut TickState,
-    tick_current: i32,
-    liquidity_delta: i128,
-    upper: bool,
-) -> Result<bool> {
-    let liquidity_gross_before = tick_state.liquidity_gross;
-    let liquidity_gross_after = add_delta(liquidity_gross_before, liquidity_delta)?;
+    /// Fee growth only, token 1 grows twice as fast as token 0
+    fn update(
+        tick_state: &mut TickState,
+        tick_current: i32,
+        liquidity_delta: i128,
+        fee_growth_global_x64: u128,
+        upper: bool,
+    ) -> bool {
+        tick_state
+            .update(
+                tick_current,
+                liquidity_delta,
+                fee_growth_global_x64,
+                fee_growth_global_x64.wrapping_mul(2),
+                0,
+                0,
+                0,
+                [0; REWARD_NUM],
+                upper,
+            )
+            .unwrap()
+    }
+
+    fn cross(tick_state: &mut TickState, fee_growth_global_x64: u128) -> i128 {
+        tick_state.cross(
+            fee_growth_global_x64,
+            fee_growth_global_x64.wrapping_mul(2),
+            0,
+            0,
+            0,
+            [0; REWARD_NUM],
+        )
+    }
+
+    fn fee_growth_inside(
+        tick_lower: &TickState,
+        tick_upper: &TickState,
+        tick_current: i32,
+        fee_growth_global_x64: u128,
+    ) -> u128 {
+        let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = get_fee_growth_inside(
+            tick_lower,
+            tick_upper,
+            tick_current,
+            fee_growth_global_x64,
+            fee_growth_global_x64.wrapping_mul(2),
+        );
+        assert_eq!(fee_growth_inside_1_x64, fee_growth_inside_0_x64.wrapping_mul(2));
+        fee_growth_inside_0_x64
+    }
 
-    let flipped = (liquidity_gross_after == 0) != (liquidity_gross_before == 0);
+    #[test]
+    fn update_counts_growth_below_an_initialized_tick_at_or_below_current() {
+        let mut below = tick_state(-10);
+        update(&mut below, 0, LIQUIDITY, 100, false);
+        assert_eq!({ below.fee_growth_outside_0_x64 }, 100);
+        assert_eq!({ below.fee_growth_outside_1_x64 }, 200);
 
-    tick_state.liquidity_gross = liquidity_gross_after;
+        let mut at = tick_state(0);
+        update(&mut at, 0, LIQUIDITY, 100, false);
+        assert_eq!({ at.fee_growth_outside_0_x64 }, 100);
 
-    tick_state.liquidity_net = if upper {
-        tick_state.liquidity_net.checked_sub(liquidity_delta)
-    } else {
-        tick_state.liquidity_net.checked_add(liquidity_delta)
+        let mut above = tick_state(10);
+        update(&mut above, 0, LIQUIDITY, 100, true);
+        assert_eq!({ above.fee_growth_outside_0_x64 }, 0);
+
+        // Only the first liquidity on a tick sets its outside growth
+        update(&mut below, 0, LIQUIDITY, 500, false);
+        assert_eq!({ below.fee_growth_outside_0_x64 }, 100);
+    }
+
+    #[test]
+    fn update_reports_flips_and_tracks_liquidity_net() {
+        let mut lower = tick_state(-10);
+        assert!(update(&mut lower, 0, LIQUIDITY, 0, false));
+        assert!(!update(&mut lower, 0, LIQUIDITY, 0, false));
+        assert_eq!({ lower.liquidity_net }, 2 * LIQUIDITY);
+        assert!(!update(&mut lower, 0, -LIQUIDITY, 0, false));
+        assert!(update(&mut lower, 0, -LIQUIDITY, 0, false));
+        assert!(!lower.is_initialized());
+
+        let mut upper = tick_state(10);
+        assert!(update(&mut upper, 0, LIQUIDITY, 0, true));
+        assert_eq!({ upper.liquidity_net }, -LIQUIDITY);
+        assert_eq!({ upper.liquidity_gross }, LIQUIDITY as u128);
+    }
+
+    #[test]
+    fn cross_flips_outside_growth_and_returns_liquidity_net() {
+        let mut upper = tick_state(10);
+        update(&mut upper, 0, LIQUIDITY, 100, true);
+
+        assert_eq!(cross(&mut upper, 150), -LIQUIDITY);
+        assert_eq!({ upper.fee_growth_outside_0_x64 }, 150);
+        assert_eq!({ upper.fee_growth_outside_1_x64 }, 300);
+
+        // Crossing back with no growth in between restores the previous value
+        cross(&mut upper, 150);
+        assert_eq!({ upper.fee_growth_outside_0_x64 }, 0);
+    }
+
+    #[test]
+    fn fee_growth_inside_follows_the_price_across_the_range() {
+        let mut lower = tick_state(-10);
+        let mut upper = tick_state(10);
+        update(&mut lower, 0, LIQUIDITY, 100, false);
+        update(&mut upper, 0, LIQUIDITY, 100, true);
+
+        // In range, everything since the ticks were initialized is inside
+        assert_eq!(fee_growth_inside(&lower, &upper, 0, 150), 50);
+
+        // Above the range, later growth stays outside
+        cross(&mut upper, 150);
+        assert_eq!(fee_growth_inside(&lower, &upper, 10, 200), 50);
+
+        // Back through the range and below it, only the 20 earned in range is added
+        cross(&mut upper, 200);
+        cross(&mut lower, 220);
+        assert_eq!(fee_growth_inside(&lower, &upper, -11, 300), 70);
+
+        // And back in range
+        cross(&mut lower, 300);
+        assert_eq!(fee_growth_inside(&lower, &upper, 0, 310), 80);
+    }
+
+    #[test]
+    fn fee_growth_inside_survives_global_wrap_around() {
+        let start = u128::MAX - 50;
+        let mut lower = tick_state(-10);
+        let mut upper = tick_state(10);
+        update(&mut lower, 0, LIQUIDITY, start, false);
+        update(&mut upper, 0, LIQUIDITY, start, true);
+
+        assert_eq!(fee_growth_inside(&lower, &upper, 0, start.wrapping_add(100)), 100);
+        cross(&mut lower, start.wrapping_add(100));
+        assert_eq!(fee_growth_inside(&lower, &upper, -11, start.wrapping_add(400)), 100);
     }
-    .unwrap();
-    Ok(flipped)
-}
\ No newline at end of file
+
+    #[test]
+    fn fee_growth_inside_of_a_range_initialized_above_the_price() {
+        // Both ticks above the current tick, nothing has been earned inside yet
+        let mut lower = tick_state(10);
+        let mut upper = tick_state(20);
+        update(&mut lower, 0, LIQUIDITY, 100, false);
+        update(&mut upper, 0, LIQUIDITY, 100, true);
+        assert_eq!(fee_growth_inside(&lower, &upper, 0, 150), 0);
+
+        cross(&mut lower, 150);
+        assert_eq!(fee_growth_inside(&lower, &upper, 15, 190), 40);
+    }
+
+    #[test]
+    fn cumulatives_inside_count_only_time_spent_in_range() {
+        // Seconds per liquidity grows by this much per second throughout
+        const SPL_PER_SECOND: u128 = 1 << 60;
+        let oracle = |tick_cumulative: i64, block_timestamp: u32| {
+            (
+                tick_cumulative,
+                block_timestamp as u128 * SPL_PER_SECOND,
+                block_timestamp,
+            )
+        };
+        let initialize = |tick_state: &mut TickState, (tick_cumulative, spl, ts), upper| {
+            tick_state
+                .update(5, LIQUIDITY, 0, 0, spl, tick_cumulative, ts, [0; REWARD_NUM], upper)
+                .unwrap();
+        };
+        let cross_at = |tick_state: &mut TickState, (tick_cumulative, spl, ts)| {
+            tick_state.cross(0, 0, spl, tick_cumulative, ts, [0; REWARD_NUM]);
+        };
+        let inside =
+            |lower: &TickState, upper: &TickState, tick_current, (tick_cumulative, spl, ts)| {
+                get_cumulatives_inside(lower, upper, tick_current, tick_cumulative, spl, ts)
+            };
+
+        // Opened at tick 5, time 1000
+        let mut lower = tick_state(-10);
+        let mut upper = tick_state(10);
+        initialize(&mut lower, oracle(5_000, 1_000), false);
+        initialize(&mut upper, oracle(5_000, 1_000), true);
+
+        // 100 seconds at tick 5
+        assert_eq!(
+            inside(&lower, &upper, 5, oracle(5_500, 1_100)),
+            (500, 100 * SPL_PER_SECOND, 100)
+        );
+
+        // Then 50 seconds at tick 15, above the range
+        cross_at(&mut upper, oracle(5_500, 1_100));
+        assert_eq!(
+            inside(&lower, &upper, 15, oracle(6_250, 1_150)),
+            (500, 100 * SPL_PER_SECOND, 100)
+        );
+
+        // Then 50 more at tick 5 before dropping below the range
+        cross_at(&mut upper, oracle(6_250, 1_150));
+        cross_at(&mut lower, oracle(6_500, 1_200));
+        assert_eq!(
+            inside(&lower, &upper, -15, oracle(5_750, 1_250)),
+            (750, 150 * SPL_PER_SECOND, 150)
+        );
+    }
+
+    #[test]
+    fn reward_growths_inside_follow_the_price_per_reward() {
+        let rewards = |growth: u128| [growth, 0, 7];
+        let mut lower = tick_state(-10);
+        let mut upper = tick_state(10);
+        for (tick_state, upper) in [(&mut lower, false), (&mut upper, true)] {
+            tick_state
+                .update(0, LIQUIDITY, 0, 0, 0, 0, 0, rewards(100), upper)
+                .unwrap();
+        }
+        let cross_at = |tick_state: &mut TickState, growth| {
+            tick_state.cross(0, 0, 0, 0, 0, rewards(growth));
+        };
+
+        assert_eq!(get_reward_growths_inside(&lower, &upper, 0, rewards(150)), [50, 0, 0]);
+
+        cross_at(&mut upper, 150);
+        assert_eq!(get_reward_growths_inside(&lower, &upper, 10, rewards(200)), [50, 0, 0]);
+
+        cross_at(&mut upper, 200);
+        cross_at(&mut lower, 220);
+        assert_eq!(get_reward_growths_inside(&lower, &upper, -11, rewards(300)), [70, 0, 0]);
+    }
+}
@@ -0,0 +1,312 @@
+use anchor_lang::prelude::*;
+use crate::errors::ClmmError;
+use crate::libraries::tick_array_bitmap::{flip_bit, is_bit_set, next_set_bit};
+use crate::states::tick_array::TickArrayState;
+use crate::util::account_load::AccountLoad;
+use crate::PoolState;
+
+pub const POOL_TICK_ARRAY_BITMAP_SEED: &str = "pool_tick_array_bitmap_extension";
+
+/// Tick arrays tracked in `PoolState`, indices -512..=511 around tick 0
+pub const TICK_ARRAY_BITMAP_SIZE: i32 = 512;
+pub const POOL_TICK_ARRAY_BITMAP_WORDS: usize = 16;
+/// Tick arrays tracked in the extension on each side of the pool bitmap
+pub const EXTENSION_TICK_ARRAY_BITMAP_SIZE: i32 = 7168;
+pub const EXTENSION_TICK_ARRAY_BITMAP_WORDS: usize = 112;
+
+impl TickArrayBitmapExtension {
+    pub const LEN: usize = 8 + 32 + 8 * EXTENSION_TICK_ARRAY_BITMAP_WORDS * 2;
+
+    pub fn initialize(&mut self, pool_id: Pubkey) {
+        self.pool_id = pool_id;
+        self.positive_tick_array_bitmap = [0; EXTENSION_TICK_ARRAY_BITMAP_WORDS];
+        self.negative_tick_array_bitmap = [0; EXTENSION_TICK_ARRAY_BITMAP_WORDS];
+    }
+}
+
+/// Initialized tick arrays beyond the range of the pool's own bitmap. Bit `i` of
+/// the positive bitmap is array index 512 + i, of the negative bitmap -513 - i
+#[account(zero_copy(unsafe))]
+#[repr(C)]
+pub struct TickArrayBitmapExtension {
+    pub pool_id: Pubkey,
+    pub positive_tick_array_bitmap: [u64; EXTENSION_TICK_ARRAY_BITMAP_WORDS],
+    pub negative_tick_array_bitmap: [u64; EXTENSION_TICK_ARRAY_BITMAP_WORDS],
+}
+
+/// Loads the optional bitmap extension and checks it belongs to `pool_id`
+pub fn load_tick_array_bitmap_extension<'info>(
+    account_info: Option<&AccountInfo<'info>>,
+    pool_id: Pubkey,
+) -> Result<Option<AccountLoad<'info, TickArrayBitmapExtension>>> {
+    match account_info {
+        Some(account_info) => {
+            let extension_loader = AccountLoad::<TickArrayBitmapExtension>::try_from(account_info)?;
+            require_keys_eq!(
+                extension_loader.load()?.pool_id,
+                pool_id,
+                ClmmError::InvalidTickArrayBitmapExtension
+            );
+            Ok(Some(extension_loader))
+        }
+        None => Ok(None),
+    }
+}
+
+fn tick_array_index(tick_array_start_index: i32, tick_spacing: u16) -> Result<i32> {
+    let index = tick_array_start_index / TickArrayState::tick_count(tick_spacing);
+    require!(
+        index.abs() < TICK_ARRAY_BITMAP_SIZE + EXTENSION_TICK_ARRAY_BITMAP_SIZE,
+        ClmmError::InvalidTickIndex
+    );
+    Ok(index)
+}
+
+pub fn flip_tick_array_bit(
+    pool_bitmap: &mut [u64; POOL_TICK_ARRAY_BITMAP_WORDS],
+    extension: Option<&mut TickArrayBitmapExtension>,
+    tick_array_start_index: i32,
+    tick_spacing: u16,
+) -> Result<()> {
+    let index = tick_array_index(tick_array_start_index, tick_spacing)?;
+    if (-TICK_ARRAY_BITMAP_SIZE..TICK_ARRAY_BITMAP_SIZE).contains(&index) {
+        flip_bit(pool_bitmap, (index + TICK_ARRAY_BITMAP_SIZE) as usize);
+        return Ok(());
+    }
+
+    let extension = extension.ok_or(ClmmError::MissingTickArrayBitmapExtension)?;
+    if index > 0 {
+        flip_bit(
+            &mut extension.positive_tick_array_bitmap,
+            (index - TICK_ARRAY_BITMAP_SIZE) as usize,
+        );
+    } else {
+        flip_bit(
+            &mut extension.negative_tick_array_bitmap,
+            (-TICK_ARRAY_BITMAP_SIZE - 1 - index) as usize,
+        );
+    }
+    Ok(())
+}
+
+pub fn is_tick_array_initialized(
+    pool_bitmap: &[u64; POOL_TICK_ARRAY_BITMAP_WORDS],
+    extension: Option<&TickArrayBitmapExtension>,
+    tick_array_start_index: i32,
+    tick_spacing: u16,
+) -> Result<bool> {
+    let index = tick_array_index(tick_array_start_index, tick_spacing)?;
+    if (-TICK_ARRAY_BITMAP_SIZE..TICK_ARRAY_BITMAP_SIZE).contains(&index) {
+        return Ok(is_bit_set(pool_bitmap, (index + TICK_ARRAY_BITMAP_SIZE) as usize));
+    }
+
+    let extension = extension.ok_or(ClmmError::MissingTickArrayBitmapExtension)?;
+    Ok(if index > 0 {
+        is_bit_set(
+            &extension.positive_tick_array_bitmap,
+            (index - TICK_ARRAY_BITMAP_SIZE) as usize,
+        )
+    } else {
+        is_bit_set(
+            &extension.negative_tick_array_bitmap,
+            (-TICK_ARRAY_BITMAP_SIZE - 1 - index) as usize,
+        )
+    })
+}
+
+/// Start index of the closest initialized tick array strictly past
+/// `tick_array_start_index` in the swap direction, if any
+pub fn next_initialized_tick_array_start_index(
+    pool_bitmap: &[u64; POOL_TICK_ARRAY_BITMAP_WORDS],
+    extension: &TickArrayBitmapExtension,
+    tick_array_start_index: i32,
+    tick_spacing: u16,
+    zero_for_one: bool,
+) -> Result<Option<i32>> {
+    let tick_count = TickArrayState::tick_count(tick_spacing);
+    let index = tick_array_index(tick_array_start_index, tick_spacing)?;
+
+    // Walk the three segments in swap direction: negative extension, pool bitmap, positive extension
+    let next_index = if zero_for_one {
+        let from = index - 1;
+        if let Some(next) = search_positive_extension(extension, from, false) {
+            Some(next)
+        } else if let Some(next) = search_pool_bitmap(pool_bitmap, from, false) {
+            Some(next)
+        } else {
+            search_negative_extension(extension, from, false)
+        }
+    } else {
+        let from = index + 1;
+        if let Some(next) = search_negative_extension(extension, from, true) {
+            Some(next)
+        } else if let Some(next) = search_pool_bitmap(pool_bitmap, from, true) {
+            Some(next)
+        } else {
+            search_positive_extension(extension, from, true)
+        }
+    };
+
+    Ok(next_index.map(|index| index * tick_count))
+}
+
+fn search_pool_bitmap(
+    pool_bitmap: &[u64; POOL_TICK_ARRAY_BITMAP_WORDS],
+    from: i32,
+    upward: bool,
+) -> Option<i32> {
+    if (upward && from >= TICK_ARRAY_BITMAP_SIZE) || (!upward && from < -TICK_ARRAY_BITMAP_SIZE) {
+        return None;
+    }
+    let from = from.clamp(-TICK_ARRAY_BITMAP_SIZE, TICK_ARRAY_BITMAP_SIZE - 1);
+    next_set_bit(pool_bitmap, (from + TICK_ARRAY_BITMAP_SIZE) as usize, upward)
+        .map(|bit| bit as i32 - TICK_ARRAY_BITMAP_SIZE)
+}
+
+fn search_positive_extension(
+    extension: &TickArrayBitmapExtension,
+    from: i32,
+    upward: bool,
+) -> Option<i32> {
+    if !upward && from < TICK_ARRAY_BITMAP_SIZE {
+        return None;
+    }
+    let from = from.max(TICK_ARRAY_BITMAP_SIZE);
+    next_set_bit(
+        &extension.positive_tick_array_bitmap,
+        (from - TICK_ARRAY_BITMAP_SIZE) as usize,
+        upward,
+    )
+    .map(|bit| bit as i32 + TICK_ARRAY_BITMAP_SIZE)
+}
+
+fn search_negative_extension(
+    extension: &TickArrayBitmapExtension,
+    from: i32,
+    upward: bool,
+) -> Option<i32> {
+    if upward && from >= -TICK_ARRAY_BITMAP_SIZE {
+        return None;
+    }
+    // Bits grow away from zero, so walking up in index walks down in bits
+    let from = from.min(-TICK_ARRAY_BITMAP_SIZE - 1);
+    next_set_bit(
+        &extension.negative_tick_array_bitmap,
+        (-TICK_ARRAY_BITMAP_SIZE - 1 - from) as usize,
+        !upward,
+    )
+    .map(|bit| -TICK_ARRAY_BITMAP_SIZE - 1 - bit as i32)
+}
+
+/// Flips the bits of tick arrays whose initialized tick count just crossed zero,
+/// loading the extension only when there is something to flip
+pub fn flip_tick_array_bits(
+    pool_state: &mut PoolState,
+    tick_array_bitmap_extension: Option<&AccountInfo>,
+    pool_id: Pubkey,
+    tick_array_start_indexes: &[i32],
+) -> Result<()> {
+    if tick_array_start_indexes.is_empty() {
+        return Ok(());
+    }
+    let extension_loader = load_tick_array_bitmap_extension(tick_array_bitmap_extension, pool_id)?;
+    let mut extension = extension_loader
+        .as_ref()
+        .map(|loader| loader.load_mut())
+        .transpose()?;
+    for tick_array_start_index in tick_array_start_indexes {
+        pool_state.flip_tick_array_bit(extension.as_deref_mut(), *tick_array_start_index)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tick_array_bitmap_extension_test {
+    use super::*;
+
+    const TICK_SPACING: u16 = 1;
+
+    fn start_index(array_index: i32) -> i32 {
+        array_index * TickArrayState::tick_count(TICK_SPACING)
+    }
+
+    /// Pool bitmap and extension with the given tick arrays initialized
+    fn bitmaps_with(
+        array_indexes: &[i32],
+    ) -> ([u64; POOL_TICK_ARRAY_BITMAP_WORDS], TickArrayBitmapExtension) {
+        let mut pool_bitmap = [0u64; POOL_TICK_ARRAY_BITMAP_WORDS];
+        let mut extension = TickArrayBitmapExtension {
+            pool_id: Pubkey::default(),
+            positive_tick_array_bitmap: [0; EXTENSION_TICK_ARRAY_BITMAP_WORDS],
+            negative_tick_array_bitmap: [0; EXTENSION_TICK_ARRAY_BITMAP_WORDS],
+        };
+        for array_index in array_indexes {
+            flip_tick_array_bit(
+                &mut pool_bitmap,
+                Some(&mut extension),
+                start_index(*array_index),
+                TICK_SPACING,
+            )
+            .unwrap();
+        }
+        (pool_bitmap, extension)
+    }
+
+    fn next(array_indexes: &[i32], from_array_index: i32, zero_for_one: bool) -> Option<i32> {
+        let (pool_bitmap, extension) = bitmaps_with(array_indexes);
+        next_initialized_tick_array_start_index(
+            &pool_bitmap,
+            &extension,
+            start_index(from_array_index),
+            TICK_SPACING,
+            zero_for_one,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn upward_from_pool_bitmap_into_positive_extension() {
+        assert_eq!(next(&[600], 500, false), Some(start_index(600)));
+        assert_eq!(next(&[512], 511, false), Some(start_index(512)));
+    }
+
+    #[test]
+    fn downward_from_positive_extension_into_pool_bitmap() {
+        assert_eq!(next(&[100], 600, true), Some(start_index(100)));
+        assert_eq!(next(&[511], 512, true), Some(start_index(511)));
+    }
+
+    #[test]
+    fn downward_from_pool_bitmap_into_negative_extension() {
+        assert_eq!(next(&[-600], -500, true), Some(start_index(-600)));
+        assert_eq!(next(&[-513], -512, true), Some(start_index(-513)));
+    }
+
+    #[test]
+    fn upward_from_negative_extension_into_pool_bitmap() {
+        assert_eq!(next(&[-100], -700, false), Some(start_index(-100)));
+        assert_eq!(next(&[-512], -513, false), Some(start_index(-512)));
+    }
+
+    #[test]
+    fn crosses_the_whole_pool_bitmap() {
+        assert_eq!(next(&[700], -700, false), Some(start_index(700)));
+        assert_eq!(next(&[-700], 700, true), Some(start_index(-700)));
+    }
+
+    #[test]
+    fn nearest_array_wins_inside_the_extension() {
+        assert_eq!(next(&[600, 900], 500, false), Some(start_index(600)));
+        assert_eq!(next(&[-600, -900], -500, true), Some(start_index(-600)));
+        assert_eq!(next(&[600, 900], 700, true), Some(start_index(600)));
+        assert_eq!(next(&[-600, -900], -700, false), Some(start_index(-600)));
+    }
+
+    #[test]
+    fn none_past_the_last_initialized_array() {
+        assert_eq!(next(&[0], 0, false), None);
+        assert_eq!(next(&[0], 0, true), None);
+        assert_eq!(next(&[], 600, false), None);
+        assert_eq!(next(&[], -600, true), None);
+    }
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+pub const TOKEN_BADGE_SEED: &str = "token_badge";
+
+impl TokenBadge {
+    pub const LEN: usize = 8 + 32 + 64;
+}
+
+/// Marks a mint as allowed in pools even if its extensions would otherwise be rejected
+#[account]
+#[derive(Default, Debug)]
+pub struct TokenBadge {
+    pub mint: Pubkey,
+    pub padding: [u64; 8],
+}
@@ -1,7 +1,7 @@
 use crate::errors::ClmmError;
 use anchor_lang::error::{Error, ErrorCode};
 use anchor_lang::solana_program::{account_info::AccountInfo, pubkey::Pubkey};
-use anchor_lang::{Key, Owner, Result, ToAccountInfos, ZeroCopy};
+use anchor_lang::{Owner, Result, ZeroCopy};
 use arrayref::array_ref;
 use std::cell::{Ref, RefMut};
 use std::marker::PhantomData;
@@ -36,7 +36,7 @@ impl<'info, T: ZeroCopy + Owner> AccountLoad<'info, T> {
         }
         // Discriminator must match.
         let disc_bytes = array_ref![data, 0, 8];
-        if disc_bytes != &T::DISCRIMINATOR {
+        if disc_bytes != T::DISCRIMINATOR {
             return Err(ClmmError::InvalidMessage.into());
         }
 
@@ -57,7 +57,7 @@ impl<'info, T: ZeroCopy + Owner> AccountLoad<'info, T> {
         Ok(AccountLoad::new(acc_info.clone()))
     }
 
-    pub fn load_init(&self) -> Result<RefMut<T>> {
+    pub fn load_init(&self) -> Result<RefMut<'_, T>> {
         // AccountInfo api allows you to borrow mut even if the account isn't
         // writable, so add this check for a better dev experience.
         if !self.acc_info.is_writable {
@@ -75,18 +75,25 @@ impl<'info, T: ZeroCopy + Owner> AccountLoad<'info, T> {
         }
 
         // write discriminator
-        data[..8].copy_from_slice(&T::DISCRIMINATOR);
+        data[..8].copy_from_slice(T::DISCRIMINATOR);
 
         Ok(RefMut::map(data, |data| {
             bytemuck::from_bytes_mut(&mut data.deref_mut()[8..mem::size_of::<T>() + 8])
         }))
     }
 
-    pub fn load_mut(&self) -> Result<RefMut<T>> {
+    pub fn load(&self) -> Result<Ref<'_, T>> {
+        let data = self.acc_info.try_borrow_data()?;
+        Ok(Ref::map(data, |data| {
+            bytemuck::from_bytes(&data[8..mem::size_of::<T>() + 8])
+        }))
+    }
+
+    pub fn load_mut(&self) -> Result<RefMut<'_, T>> {
         if !self.acc_info.is_writable {
             return Err(ErrorCode::AccountNotMutable.into());
         }
-        let mut data = self.acc_info.try_borrow_mut_data()?;
+        let data = self.acc_info.try_borrow_mut_data()?;
         Ok(RefMut::map(data, |data| {
             bytemuck::from_bytes_mut(&mut data.deref_mut()[8..mem::size_of::<T>() + 8])
         }))
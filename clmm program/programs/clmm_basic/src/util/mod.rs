@@ -1,5 +1,7 @@
 pub mod token;
 pub mod account_load;
+pub mod position_authority;
 
 pub use token::*;
-pub use account_load::*;
\ No newline at end of file
+pub use account_load::*;
+pub use position_authority::*;
\ No newline at end of file
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, TokenAccount};
+use mpl_core::accounts::BaseAssetV1;
+use crate::errors::ClmmError;
+use crate::states::personal_position::{PersonalPositionState, PositionKind};
+
+/// Checks that `owner` controls the position, either by holding its SPL NFT in
+/// `position_token` or by owning the mpl-core asset passed as `position_token`
+pub fn require_position_authority<'info>(
+    personal_position: &PersonalPositionState,
+    owner: &AccountInfo<'info>,
+    position_token: &AccountInfo<'info>,
+) -> Result<()> {
+    match personal_position.position_kind {
+        PositionKind::SplNft => {
+            require_keys_eq!(*position_token.owner, token::ID, ClmmError::NotApproved);
+            let nft_account = TokenAccount::try_deserialize(&mut &position_token.try_borrow_data()?[..])?;
+            require_keys_eq!(nft_account.mint, personal_position.nft_mint, ClmmError::NotApproved);
+            require_keys_eq!(nft_account.owner, owner.key(), ClmmError::NotApproved);
+            require_eq!(nft_account.amount, 1, ClmmError::NotApproved);
+        }
+        PositionKind::CoreAsset => {
+            require_keys_eq!(position_token.key(), personal_position.nft_mint, ClmmError::NotApproved);
+            require_keys_eq!(*position_token.owner, mpl_core::ID, ClmmError::NotApproved);
+            let asset = BaseAssetV1::from_bytes(&position_token.try_borrow_data()?)
+                .map_err(|_| ClmmError::NotApproved)?;
+            require_keys_eq!(asset.owner, owner.key(), ClmmError::NotApproved);
+        }
+    }
+    Ok(())
+}
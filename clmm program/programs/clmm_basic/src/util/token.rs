@@ -1,11 +1,34 @@
 use anchor_lang::{prelude::*, system_program};
 use anchor_spl::token_2022::{
-    self, get_account_data_size, GetAccountDataSize, InitializeAccount3, InitializeImmutableOwner,
-    Token2022,
+    self, GetAccountDataSize, InitializeAccount3, InitializeImmutableOwner,
+};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        default_account_state::DefaultAccountState,
+        transfer_fee::{TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+        transfer_hook::TransferHook,
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    state::AccountState,
 };
 use anchor_spl::token_interface::{Mint, TokenInterface};
-use anchor_spl::token::{self, Token};
+use anchor_spl::token;
 use crate::PoolState;
+use crate::errors::ClmmError;
+
+pub const POOL_SEED: &str = "pool_seed";
+
+/// Seeds of the pool PDA, which signs for its vaults and position mints
+pub fn pool_signer_seeds(pool: &PoolState) -> [&[u8]; 5] {
+    [
+        POOL_SEED.as_bytes(),
+        pool.amm_config.as_ref(),
+        pool.token_mint_0.as_ref(),
+        pool.token_mint_1.as_ref(),
+        std::slice::from_ref(&pool.bump),
+    ]
+}
 
 pub fn transfer_from_user_to_pool_vault<'info>(
     // signer: &Signer<'info>,
@@ -64,17 +87,8 @@ pub fn transfer_from_pool_vault_to_user<'info>(
     token_program_2022: Option<AccountInfo<'info>>,
     amount: u64,
 ) -> Result<()> {
-    pub const POOL_SEED: &str = "pool_seed";
-
     let pool = pool_state_loader.load()?;
-    let bump = [pool.bump];
-
-    let signer_seeds: &[&[u8]] = &[
-            POOL_SEED.as_bytes(),
-            pool.token_mint_0.as_ref(),
-            pool.token_mint_1.as_ref(),
-            &bump,
-        ];
+    let signer_seeds: &[&[u8]] = &pool_signer_seeds(&pool);
 
     if amount == 0 {
         return Ok(());
@@ -116,6 +130,121 @@ pub fn transfer_from_pool_vault_to_user<'info>(
     }
 }
 
+/// Fee withheld by the mint's TransferFee extension when `pre_fee_amount` is sent
+pub fn get_transfer_fee(mint: Option<&InterfaceAccount<Mint>>, pre_fee_amount: u64) -> Result<u64> {
+    let mint_info = match mint {
+        Some(mint) => mint.to_account_info(),
+        None => return Ok(0),
+    };
+    if *mint_info.owner == token::ID {
+        return Ok(0);
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    let fee = if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+        epoch_transfer_fee(transfer_fee_config, Clock::get()?.epoch, pre_fee_amount)?
+    } else {
+        0
+    };
+    Ok(fee)
+}
+
+/// Fee to send on top of `post_fee_amount` so the receiver gets exactly `post_fee_amount`
+pub fn get_transfer_inverse_fee(
+    mint: Option<&InterfaceAccount<Mint>>,
+    post_fee_amount: u64,
+) -> Result<u64> {
+    let mint_info = match mint {
+        Some(mint) => mint.to_account_info(),
+        None => return Ok(0),
+    };
+    if *mint_info.owner == token::ID {
+        return Ok(0);
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    let fee = if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+        epoch_transfer_inverse_fee(transfer_fee_config, Clock::get()?.epoch, post_fee_amount)?
+    } else {
+        0
+    };
+    Ok(fee)
+}
+
+/// Fee withheld from `pre_fee_amount` under the transfer fee in force at `epoch`
+fn epoch_transfer_fee(
+    transfer_fee_config: &TransferFeeConfig,
+    epoch: u64,
+    pre_fee_amount: u64,
+) -> Result<u64> {
+    Ok(transfer_fee_config
+        .calculate_epoch_fee(epoch, pre_fee_amount)
+        .ok_or(ClmmError::CalculateOverflow)?)
+}
+
+/// Fee on top of `post_fee_amount` under the transfer fee in force at `epoch`
+fn epoch_transfer_inverse_fee(
+    transfer_fee_config: &TransferFeeConfig,
+    epoch: u64,
+    post_fee_amount: u64,
+) -> Result<u64> {
+    let transfer_fee = transfer_fee_config.get_epoch_fee(epoch);
+    // A 100% fee has no inverse, the sender always pays the maximum
+    if u16::from(transfer_fee.transfer_fee_basis_points) == MAX_FEE_BASIS_POINTS {
+        return Ok(u64::from(transfer_fee.maximum_fee));
+    }
+    Ok(transfer_fee_config
+        .calculate_inverse_epoch_fee(epoch, post_fee_amount)
+        .ok_or(ClmmError::CalculateOverflow)?)
+}
+
+/// Rejects Token-2022 mints whose extensions would let tokens leave or freeze in the
+/// vaults behind the pool's back, or make transfers the pool cannot complete
+pub fn check_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == token::ID {
+        return Ok(());
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    for extension_type in mint.get_extension_types()? {
+        match extension_type {
+            ExtensionType::TransferFeeConfig
+            | ExtensionType::MintCloseAuthority
+            | ExtensionType::InterestBearingConfig
+            | ExtensionType::MetadataPointer
+            | ExtensionType::TokenMetadata
+            | ExtensionType::GroupPointer
+            | ExtensionType::TokenGroup
+            | ExtensionType::GroupMemberPointer
+            | ExtensionType::TokenGroupMember => {}
+            ExtensionType::PermanentDelegate => {
+                return err!(ClmmError::UnsupportedMintPermanentDelegate)
+            }
+            ExtensionType::NonTransferable => {
+                return err!(ClmmError::UnsupportedMintNonTransferable)
+            }
+            ExtensionType::DefaultAccountState => {
+                let default_account_state = mint.get_extension::<DefaultAccountState>()?;
+                if default_account_state.state == AccountState::Frozen as u8 {
+                    return err!(ClmmError::UnsupportedMintDefaultFrozen);
+                }
+            }
+            ExtensionType::TransferHook => {
+                let transfer_hook = mint.get_extension::<TransferHook>()?;
+                if Option::<Pubkey>::from(transfer_hook.program_id).is_some() {
+                    return err!(ClmmError::UnsupportedMintTransferHook);
+                }
+            }
+            _ => return err!(ClmmError::UnsupportedMintExtension),
+        }
+    }
+    Ok(())
+}
+
 pub fn create_token_vault_account<'info>(
     payer: &Signer<'info>,
     pool_state: &AccountInfo<'info>,
@@ -164,7 +293,7 @@ pub fn create_token_vault_account<'info>(
             mint: token_mint.to_account_info(),
             authority: pool_state.to_account_info(),
         },
-    ));
+    ))?;
 
     Ok(())
 }
@@ -223,4 +352,75 @@ pub fn create_or_allocate_account<'a>(
         system_program::assign(cpi_context.with_signer(&[siger_seed]), program_id)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod token_test {
+    use super::*;
+    use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
+
+    const NEWER_EPOCH: u64 = 10;
+
+    /// 1% until NEWER_EPOCH, `newer_basis_points` from then on
+    fn transfer_fee_config(newer_basis_points: u16, maximum_fee: u64) -> TransferFeeConfig {
+        TransferFeeConfig {
+            older_transfer_fee: TransferFee {
+                epoch: 0.into(),
+                maximum_fee: maximum_fee.into(),
+                transfer_fee_basis_points: 100.into(),
+            },
+            newer_transfer_fee: TransferFee {
+                epoch: NEWER_EPOCH.into(),
+                maximum_fee: maximum_fee.into(),
+                transfer_fee_basis_points: newer_basis_points.into(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn transfer_fee_rounds_up_and_caps() {
+        let config = transfer_fee_config(100, 1_000);
+        assert_eq!(epoch_transfer_fee(&config, 0, 10_000).unwrap(), 100);
+        assert_eq!(epoch_transfer_fee(&config, 0, 1).unwrap(), 1);
+        assert_eq!(epoch_transfer_fee(&config, 0, 0).unwrap(), 0);
+        assert_eq!(epoch_transfer_fee(&config, 0, 10_000_000).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn transfer_fee_follows_the_epoch() {
+        let config = transfer_fee_config(500, u64::MAX);
+        assert_eq!(epoch_transfer_fee(&config, NEWER_EPOCH - 1, 10_000).unwrap(), 100);
+        assert_eq!(epoch_transfer_fee(&config, NEWER_EPOCH, 10_000).unwrap(), 500);
+        assert_eq!(epoch_transfer_inverse_fee(&config, NEWER_EPOCH - 1, 9_900).unwrap(), 100);
+        assert_eq!(epoch_transfer_inverse_fee(&config, NEWER_EPOCH, 9_500).unwrap(), 500);
+    }
+
+    #[test]
+    fn inverse_fee_delivers_the_exact_amount() {
+        let fee_schedules = [(1, u64::MAX), (250, u64::MAX), (9_999, u64::MAX), (300, 5_000)];
+        for (basis_points, maximum_fee) in fee_schedules {
+            let config = transfer_fee_config(basis_points, maximum_fee);
+            for post_fee_amount in [1, 99, 10_000, 1_234_567, u64::from(u32::MAX)] {
+                let pre_fee_amount = post_fee_amount
+                    + epoch_transfer_inverse_fee(&config, NEWER_EPOCH, post_fee_amount).unwrap();
+                let fee = epoch_transfer_fee(&config, NEWER_EPOCH, pre_fee_amount).unwrap();
+                assert_eq!(
+                    pre_fee_amount - fee,
+                    post_fee_amount,
+                    "{} bps, {} out",
+                    basis_points,
+                    post_fee_amount
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn full_fee_charges_the_maximum() {
+        let config = transfer_fee_config(MAX_FEE_BASIS_POINTS, 7_000);
+        assert_eq!(epoch_transfer_inverse_fee(&config, NEWER_EPOCH, 1).unwrap(), 7_000);
+        assert_eq!(epoch_transfer_inverse_fee(&config, NEWER_EPOCH, 1_000_000).unwrap(), 7_000);
+        assert_eq!(epoch_transfer_fee(&config, NEWER_EPOCH, 1_000_000).unwrap(), 7_000);
+    }
+}